@@ -0,0 +1,334 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use markdown::ParseOptions;
+use markdown::mdast::Node;
+
+use crate::code;
+use crate::extension::{self, Edit};
+
+const ALERTS: &[&str] = &["note", "tip", "important", "warning", "caution"];
+
+#[derive(Debug, PartialEq)]
+pub struct Admonition {
+    kind: String,
+    title: String,
+}
+
+/// The admonitions found in a source, and the characters their placeholders are made of.
+#[derive(Debug)]
+pub struct Admonitions {
+    start: char,
+    end: char,
+    found: Vec<Admonition>,
+}
+
+impl Admonitions {
+    fn open(&mut self, kind: &str, title: &str) -> String {
+        let title = if title.is_empty() {
+            let mut chars = kind.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        } else {
+            title.to_string()
+        };
+        self.found.push(Admonition {
+            kind: kind.to_string(),
+            title,
+        });
+        format!("{}{}{}", self.start, self.found.len() - 1, self.start)
+    }
+
+    fn close(&self) -> String {
+        self.end.to_string()
+    }
+}
+
+/// Replace alert blockquotes and `:::kind` fences by placeholder paragraphs around their
+/// contents, so the contents are still converted as markdown.
+pub fn expand(source: &str, options: &ParseOptions) -> anyhow::Result<(String, Admonitions)> {
+    let mut unused = extension::unused_chars(source);
+    let (Some(start), Some(end)) = (unused.next(), unused.next()) else {
+        anyhow::bail!("no characters left to use as placeholders");
+    };
+    let mut admonitions = Admonitions {
+        start,
+        end,
+        found: Vec::new(),
+    };
+
+    // an alert inside another one is only found once the outer one is replaced
+    let mut source = source.to_string();
+    loop {
+        let count = admonitions.found.len();
+        source = expand_alerts(&source, options, &mut admonitions)?;
+        if admonitions.found.len() == count {
+            break;
+        }
+    }
+    let source = expand_fences(&source, &mut admonitions);
+    Ok((source, admonitions))
+}
+
+/// Turn the placeholders left by [`expand`] in converted html into `<div class="admonition">`.
+pub fn finish(html: &str, admonitions: &Admonitions) -> String {
+    let mut html = html.replace(&format!("<p>{}</p>", admonitions.end), "</div>");
+    for (i, a) in admonitions.found.iter().enumerate() {
+        let open = format!(
+            "<div class=\"admonition {}\">\n<p class=\"admonition-title\">{}</p>",
+            a.kind,
+            escape(&a.title)
+        );
+        let start = admonitions.start;
+        html = html.replace(&format!("<p>{}{}{}</p>", start, i, start), &open);
+    }
+    html
+}
+
+fn expand_alerts(
+    source: &str,
+    options: &ParseOptions,
+    admonitions: &mut Admonitions,
+) -> anyhow::Result<String> {
+    let root = match markdown::to_mdast(source, options) {
+        Ok(root) => root,
+        Err(e) => anyhow::bail!("{}", e),
+    };
+    let mut edits = Vec::new();
+    find_alerts(&root, source, 0, admonitions, &mut edits);
+    Ok(extension::apply(source, edits))
+}
+
+/// Replace the alerts below `node`, which is inside `quotes` blockquotes.
+fn find_alerts(
+    node: &Node,
+    source: &str,
+    quotes: usize,
+    admonitions: &mut Admonitions,
+    edits: &mut Vec<Edit>,
+) {
+    for child in node.children().into_iter().flatten() {
+        if let (Node::Blockquote(quote), Some(pos)) = (child, child.position())
+            && !quote.children.is_empty()
+        {
+            let range = pos.start.offset..pos.end.offset;
+            // the first line starts at the `>` of this blockquote, the others at the line start
+            let mut lines = source[range.clone()].lines();
+            let first = lines.next().map(|l| unquote(l, 1));
+            if let Some((kind, title)) = first.and_then(alert) {
+                let lines: Vec<&str> = lines.map(|l| unquote(l, quotes + 1)).collect();
+                let replacement =
+                    alert_placeholders(source, range.start, admonitions, kind, title, &lines);
+                edits.push((range, replacement));
+                continue;
+            }
+        }
+        let quotes = quotes + usize::from(matches!(child, Node::Blockquote(_)));
+        find_alerts(child, source, quotes, admonitions, edits);
+    }
+}
+
+/// Paragraphs standing in for an alert starting at `start`, continuing the blockquotes and list
+/// items it is in.
+fn alert_placeholders(
+    source: &str,
+    start: usize,
+    admonitions: &mut Admonitions,
+    kind: &str,
+    title: &str,
+    lines: &[&str],
+) -> String {
+    let prefix = extension::container_prefix(source, start);
+    let blank = prefix.trim_end();
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    // after a list marker the alert starts the item, otherwise it can follow a paragraph
+    let starts_item = source[line_start..start]
+        .chars()
+        .any(|c| c != '>' && !c.is_whitespace());
+
+    let mut out = String::new();
+    if !starts_item {
+        out.push_str(&format!("\n{}\n{}", blank, prefix));
+    }
+    out.push_str(&admonitions.open(kind, title));
+    let body = std::iter::once("").chain(lines.iter().copied()).chain([""]);
+    for line in body {
+        match line {
+            "" => out.push_str(&format!("\n{}", blank)),
+            line => out.push_str(&format!("\n{}{}", prefix, line)),
+        }
+    }
+    out.push_str(&format!("\n{}{}\n{}", prefix, admonitions.close(), blank));
+    out
+}
+
+fn expand_fences(source: &str, admonitions: &mut Admonitions) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let code = code::ranges(source, false);
+    let mut open_at = Vec::new();
+    let mut pairs = Vec::new();
+    let mut offset = 0;
+
+    for (i, line) in source.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim();
+        let start = offset;
+        offset += line.len();
+        if code::in_code(&code, start, line) {
+            continue;
+        }
+        if trimmed == ":::" {
+            if let Some(first) = open_at.pop() {
+                pairs.push((first, i));
+            }
+        } else if trimmed.starts_with(":::") {
+            open_at.push(i);
+        }
+    }
+
+    if pairs.is_empty() {
+        return source.to_string();
+    }
+
+    let mut replaced: Vec<Option<String>> = vec![None; lines.len()];
+    pairs.sort();
+    for (first, last) in pairs {
+        let rest = lines[first].trim().trim_start_matches(':').trim();
+        let (kind, title) = rest.split_once(' ').unwrap_or((rest, ""));
+        let kind = kind.to_lowercase();
+        if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            continue;
+        }
+        replaced[first] = Some(format!("\n{}\n\n", admonitions.open(&kind, title.trim())));
+        replaced[last] = Some(format!("\n\n{}\n", admonitions.close()));
+    }
+
+    let mut out: Vec<&str> = Vec::new();
+    for (line, replacement) in lines.iter().zip(replaced.iter()) {
+        out.push(replacement.as_deref().unwrap_or(line));
+    }
+    let mut out = out.join("\n");
+    if source.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+/// Strip the `>` markers of `quotes` nested blockquotes from a line, lazy lines are kept as
+/// they are.
+fn unquote(line: &str, quotes: usize) -> &str {
+    let mut line = line;
+    for _ in 0..quotes {
+        match line.trim_start().strip_prefix('>') {
+            Some(rest) => line = rest.strip_prefix(' ').unwrap_or(rest),
+            None => break,
+        }
+    }
+    line
+}
+
+fn alert(line: &str) -> Option<(&'static str, &str)> {
+    let rest = line.trim().strip_prefix("[!")?;
+    let (kind, title) = rest.split_once(']')?;
+    let kind = ALERTS.iter().find(|a| a.eq_ignore_ascii_case(kind))?;
+    Some((kind, title.trim()))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(source: &str) -> String {
+        let options = markdown::Options::gfm();
+        let (source, admonitions) = expand(source, &options.parse).unwrap();
+        let html = markdown::to_html_with_options(&source, &options).unwrap();
+        finish(&html, &admonitions)
+    }
+
+    #[test]
+    fn alerts() {
+        let res = convert("Text\n> [!WARNING]\n> Mind the *gap*.\n\n> Just a quote\n");
+        let exp = "<p>Text</p>
+<div class=\"admonition warning\">
+<p class=\"admonition-title\">Warning</p>
+<p>Mind the <em>gap</em>.</p>
+</div>
+<blockquote>
+<p>Just a quote</p>
+</blockquote>
+";
+
+        assert_eq!(res, exp);
+    }
+
+    #[test]
+    fn fences() {
+        let res =
+            convert(":::tip Use <kbd>\nNested:\n\n:::note\nInner\n:::\n:::\n\n```\n:::no\n```\n");
+        let exp = "<div class=\"admonition tip\">
+<p class=\"admonition-title\">Use &lt;kbd&gt;</p>
+<p>Nested:</p>
+<div class=\"admonition note\">
+<p class=\"admonition-title\">Note</p>
+<p>Inner</p>
+</div>
+</div>
+<pre><code>:::no
+</code></pre>
+";
+
+        assert_eq!(res, exp);
+    }
+
+    #[test]
+    fn nested_alerts() {
+        let res = convert(
+            "- > [!NOTE]\n  > In a list\n- next\n\n\
+             > [!TIP]\n> > [!CAUTION] Inner\n> > Deep\n>\n> Outer\n",
+        );
+        let exp = "<ul>
+<li>
+<div class=\"admonition note\">
+<p class=\"admonition-title\">Note</p>
+<p>In a list</p>
+</div>
+</li>
+<li>
+<p>next</p>
+</li>
+</ul>
+<div class=\"admonition tip\">
+<p class=\"admonition-title\">Tip</p>
+<div class=\"admonition caution\">
+<p class=\"admonition-title\">Inner</p>
+<p>Deep</p>
+</div>
+<p>Outer</p>
+</div>
+";
+
+        assert_eq!(res, exp);
+    }
+
+    #[test]
+    fn placeholders_in_source() {
+        let res = convert("\u{E000}0\u{E000}\n\n&#xE001;\n\n> [!NOTE]\n> Text\n");
+        let exp = "<p>\u{E000}0\u{E000}</p>
+<p>\u{E001}</p>
+<div class=\"admonition note\">
+<p class=\"admonition-title\">Note</p>
+<p>Text</p>
+</div>
+";
+
+        assert_eq!(res, exp);
+    }
+}
@@ -0,0 +1,287 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::fs;
+use std::path::Path;
+
+use biblatex::{Bibliography, ChunksExt, DateValue, Entry, PermissiveType};
+
+use crate::code;
+use crate::spec::CitationStyle;
+
+/// Read and parse a BibTeX file.
+pub fn load(path: &Path) -> anyhow::Result<Bibliography> {
+    let source = match fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) => anyhow::bail!("cannot read bibliography {}: {}", path.display(), e),
+    };
+    match Bibliography::parse(&source) {
+        Ok(bib) => Ok(bib),
+        Err(e) => anyhow::bail!("{}: {}", path.display(), e),
+    }
+}
+
+/// Replace `[@key]` and `[@key1; @key2]` citations in page markdown by links to the
+/// references list, returning the cited entries in the order of their numbers.
+pub fn cite<'a>(
+    source: &str,
+    bib: &'a Bibliography,
+    style: CitationStyle,
+) -> (String, Vec<&'a Entry>) {
+    let mut cited: Vec<&Entry> = Vec::new();
+    let mut out = String::new();
+    let code = code::ranges(source, true);
+    let mut last = 0;
+
+    for (start, _) in source.match_indices('[') {
+        if start < last || code::contains(&code, start) {
+            continue;
+        }
+        // a citation does not span lines
+        let end = source[start..].find([']', '\n']).map(|e| start + e);
+        let Some(end) = end.filter(|&e| source[e..].starts_with(']')) else {
+            continue;
+        };
+        let Some(keys) = keys(&source[start + 1..end]) else {
+            continue;
+        };
+        let entries = keys
+            .iter()
+            .map(|k| bib.get(k))
+            .collect::<Option<Vec<&Entry>>>();
+        let Some(entries) = entries else {
+            log::warn!("Unknown citation {}", &source[start..=end]);
+            continue;
+        };
+
+        for entry in &entries {
+            if !cited.iter().any(|c| c.key == entry.key) {
+                cited.push(entry);
+            }
+        }
+        out.push_str(&source[last..start]);
+        out.push_str(&citation_link(&entries, &cited, style));
+        last = end + 1;
+    }
+    out.push_str(&source[last..]);
+
+    (out, cited)
+}
+
+fn keys(inner: &str) -> Option<Vec<&str>> {
+    inner
+        .split(';')
+        .map(|k| {
+            let key = k.trim().strip_prefix('@')?;
+            let valid = !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "-_:.".contains(c));
+            valid.then_some(key)
+        })
+        .collect()
+}
+
+fn citation_link(entries: &[&Entry], cited: &[&Entry], style: CitationStyle) -> String {
+    let links: Vec<String> = entries
+        .iter()
+        .map(|e| {
+            let label = match style {
+                CitationStyle::Numbered => {
+                    let n = cited.iter().position(|c| c.key == e.key).unwrap_or(0) + 1;
+                    n.to_string()
+                }
+                CitationStyle::AuthorYear => author_year(e),
+            };
+            format!("[{}](#ref-{})", label, e.key)
+        })
+        .collect();
+
+    match style {
+        CitationStyle::Numbered => format!("\\[{}\\]", links.join(", ")),
+        CitationStyle::AuthorYear => format!("({})", links.join("; ")),
+    }
+}
+
+/// The html list of `cited` entries, appended to the page content.
+pub fn references(cited: &[&Entry], style: CitationStyle) -> String {
+    if cited.is_empty() {
+        return String::new();
+    }
+
+    let mut entries = cited.to_vec();
+    let tag = match style {
+        CitationStyle::Numbered => "ol",
+        CitationStyle::AuthorYear => {
+            entries.sort_by_key(|e| author_year(e).to_lowercase());
+            "ul"
+        }
+    };
+
+    let mut html = format!(
+        "<section class=\"references\">\n<h2>References</h2>\n<{}>\n",
+        tag
+    );
+    for entry in entries {
+        html.push_str(&format!(
+            "<li id=\"ref-{}\">{}</li>\n",
+            escape(&entry.key),
+            format_entry(entry)
+        ));
+    }
+    html.push_str(&format!("</{}>\n</section>\n", tag));
+    html
+}
+
+fn author_year(entry: &Entry) -> String {
+    let authors = entry.author().unwrap_or_default();
+    let names = match authors.as_slice() {
+        [] => title(entry),
+        [a] => a.name.clone(),
+        [a, b] => format!("{} and {}", a.name, b.name),
+        [a, ..] => format!("{} et al.", a.name),
+    };
+    match year(entry) {
+        Some(year) => format!("{} {}", names, year),
+        None => names,
+    }
+}
+
+fn format_entry(entry: &Entry) -> String {
+    let mut parts = Vec::new();
+
+    let authors: Vec<String> = entry
+        .author()
+        .unwrap_or_default()
+        .iter()
+        .map(|p| {
+            if p.given_name.is_empty() {
+                p.name.clone()
+            } else {
+                format!("{}, {}", p.name, p.given_name)
+            }
+        })
+        .collect();
+    let mut head = match authors.as_slice() {
+        [] => String::new(),
+        [a] => a.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    };
+    if let Some(year) = year(entry) {
+        head = format!("{} ({})", head, year).trim_start().to_string();
+    }
+    if !head.is_empty() {
+        parts.push(escape(&head));
+    }
+
+    parts.push(format!("<em>{}</em>", escape(&title(entry))));
+
+    let container = entry
+        .journal()
+        .or_else(|_| entry.book_title())
+        .map(|c| c.format_verbatim())
+        .ok()
+        .or_else(|| {
+            let publisher = entry.publisher().ok()?;
+            Some(
+                publisher
+                    .iter()
+                    .map(|p| p.format_verbatim())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+        });
+    if let Some(mut container) = container {
+        if let Ok(PermissiveType::Typed(volume)) = entry.volume() {
+            container = format!("{}, {}", container, volume);
+        }
+        parts.push(escape(&container));
+    }
+
+    if let Ok(doi) = entry.doi() {
+        parts.push(format!(
+            "<a href=\"https://doi.org/{}\">doi:{}</a>",
+            escape(&doi),
+            escape(&doi)
+        ));
+    } else if let Ok(url) = entry.url() {
+        parts.push(format!("<a href=\"{}\">{}</a>", escape(&url), escape(&url)));
+    }
+
+    parts.join(". ") + "."
+}
+
+fn title(entry: &Entry) -> String {
+    entry
+        .title()
+        .map(|t| t.format_sentence())
+        .unwrap_or_else(|_| entry.key.clone())
+}
+
+fn year(entry: &Entry) -> Option<i32> {
+    let PermissiveType::Typed(date) = entry.date().ok()? else {
+        return None;
+    };
+    match date.value {
+        DateValue::At(d)
+        | DateValue::After(d)
+        | DateValue::Before(d)
+        | DateValue::Between(d, _) => Some(d.year),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbered() -> anyhow::Result<()> {
+        let bib = load(Path::new("tests/fixtures/spec4/refs.bib"))?;
+        let source = "See [@knuth84; @lamport94] and [@knuth84].\n`[@knuth84]` [@missing] [link]\n\
+                      [@knuth84\n\n    [@knuth84]\n";
+        let (res, cited) = cite(source, &bib, CitationStyle::Numbered);
+        let exp = "See \\[[1](#ref-knuth84), [2](#ref-lamport94)\\] and \\[[1](#ref-knuth84)\\].
+`[@knuth84]` [@missing] [link]
+[@knuth84
+
+    [@knuth84]
+";
+
+        assert_eq!(res, exp);
+        assert_eq!(cited.len(), 2);
+        assert_eq!(
+            references(&cited, CitationStyle::Numbered),
+            "<section class=\"references\">
+<h2>References</h2>
+<ol>
+<li id=\"ref-knuth84\">Knuth, Donald E. (1984). <em>Literate programming</em>. \
+The Computer Journal, 27. <a href=\"https://doi.org/10.1093/comjnl/27.2.97\">\
+doi:10.1093/comjnl/27.2.97</a>.</li>
+<li id=\"ref-lamport94\">Lamport, Leslie (1994). <em>LaTeX: a document preparation system</em>. \
+Addison-Wesley.</li>
+</ol>
+</section>
+"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn author_year_style() -> anyhow::Result<()> {
+        let bib = load(Path::new("tests/fixtures/spec4/refs.bib"))?;
+        let (res, _) = cite("[@lamport94; @gof]", &bib, CitationStyle::AuthorYear);
+
+        assert_eq!(
+            res,
+            "([Lamport 1994](#ref-lamport94); [Gamma et al. 1994](#ref-gof))"
+        );
+        Ok(())
+    }
+}
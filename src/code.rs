@@ -0,0 +1,111 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::ops::Range;
+
+use markdown::mdast::Node;
+use markdown::{Constructs, ParseOptions};
+
+/// Byte ranges of the code blocks in markdown `source`, fenced, indented or math, and with
+/// `inline` also of the code spans and inline math.
+pub fn ranges(source: &str, inline: bool) -> Vec<Range<usize>> {
+    let options = ParseOptions {
+        constructs: Constructs {
+            frontmatter: true,
+            math_flow: true,
+            math_text: true,
+            ..Constructs::gfm()
+        },
+        ..ParseOptions::gfm()
+    };
+    // only mdx can fail to parse
+    let Ok(root) = markdown::to_mdast(source, &options) else {
+        return Vec::new();
+    };
+    let mut ranges = Vec::new();
+    collect(&root, inline, &mut ranges);
+    ranges
+}
+
+/// Whether `line`, starting at `offset` in the source, is part of one of the code `ranges`.
+pub fn in_code(ranges: &[Range<usize>], offset: usize, line: &str) -> bool {
+    let indent = match line.trim_start() {
+        "" => 0,
+        rest => line.len() - rest.len(),
+    };
+    contains(ranges, offset + indent)
+}
+
+pub fn contains(ranges: &[Range<usize>], offset: usize) -> bool {
+    ranges.iter().any(|r| r.contains(&offset))
+}
+
+fn collect(node: &Node, inline: bool, ranges: &mut Vec<Range<usize>>) {
+    let code = match node {
+        Node::Code(_) | Node::Math(_) => true,
+        Node::InlineCode(_) | Node::InlineMath(_) => inline,
+        _ => false,
+    };
+    match node.position() {
+        Some(p) if code => ranges.push(p.start.offset..p.end.offset),
+        _ => {
+            for child in node.children().into_iter().flatten() {
+                collect(child, inline, ranges);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines_in_code(source: &str) -> Vec<&str> {
+        let ranges = ranges(source, false);
+        let mut offset = 0;
+        let mut lines = Vec::new();
+        for line in source.split_inclusive('\n') {
+            if in_code(&ranges, offset, line) {
+                lines.push(line.trim());
+            }
+            offset += line.len();
+        }
+        lines
+    }
+
+    #[test]
+    fn fences() {
+        let source = "````\n```\nstill code\n````\ntext\n~~~ rust\n```\n~~~\n";
+
+        assert_eq!(
+            lines_in_code(source),
+            [
+                "````",
+                "```",
+                "still code",
+                "````",
+                "~~~ rust",
+                "```",
+                "~~~"
+            ]
+        );
+    }
+
+    #[test]
+    fn indented_code() {
+        let source = "text\n\n    ```\n    code\n\n- item\n\n  ```\n  listed\n  ```\n";
+
+        assert_eq!(
+            lines_in_code(source),
+            ["```", "code", "```", "listed", "```"]
+        );
+    }
+
+    #[test]
+    fn inline_code() {
+        let source = "a `[@key]` b $x$ c";
+
+        assert!(ranges(source, false).is_empty());
+        assert_eq!(ranges(source, true), [2..10, 13..16]);
+    }
+}
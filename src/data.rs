@@ -0,0 +1,101 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use minijinja::Value;
+
+/// Directory next to the manifest with structured data available to templates.
+pub const DATA_DIR: &str = "data";
+
+/// Read every TOML, JSON and CSV file in `dir` into one object keyed by file stem.
+pub fn load(dir: &Path) -> anyhow::Result<Value> {
+    let Ok(read_dir) = dir.read_dir() else {
+        return Ok(Value::from(BTreeMap::<String, Value>::new()));
+    };
+    let mut paths: Vec<_> = read_dir.flatten().map(|e| e.path()).collect();
+    paths.sort();
+
+    let mut data = BTreeMap::new();
+    for path in paths {
+        let Some(stem) = path.file_stem() else {
+            continue;
+        };
+        let stem = stem.to_string_lossy().to_string();
+        if stem.starts_with('.') {
+            continue;
+        }
+
+        let value = if path.is_dir() {
+            load(&path)?
+        } else {
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("toml") => Value::from_serialize(parse_toml(&path)?),
+                Some("json") => Value::from_serialize(parse_json(&path)?),
+                Some("csv") => Value::from_serialize(parse_csv(&path)?),
+                _ => {
+                    log::warn!("Ignoring {}: not a TOML, JSON or CSV file", path.display());
+                    continue;
+                }
+            }
+        };
+
+        log::trace!("Loaded data from {} as `{}`", path.display(), stem);
+        if data.insert(stem, value).is_some() {
+            anyhow::bail!(
+                "{} has the same name as another data file or directory",
+                path.display()
+            );
+        }
+    }
+
+    Ok(Value::from(data))
+}
+
+fn parse_toml(path: &Path) -> anyhow::Result<toml::Table> {
+    let source = fs::read_to_string(path)?;
+    toml::from_str(&source).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+}
+
+fn parse_json(path: &Path) -> anyhow::Result<serde_json::Value> {
+    let source = fs::read_to_string(path)?;
+    serde_json::from_str(&source).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+}
+
+fn parse_csv(path: &Path) -> anyhow::Result<Vec<BTreeMap<String, String>>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let mut rows = Vec::new();
+    for row in reader.deserialize() {
+        rows.push(row.map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_data() -> anyhow::Result<()> {
+        let mut env = minijinja::Environment::new();
+        env.add_global("data", load(Path::new("tests/fixtures/spec3/data"))?);
+        let res = env.render_str(
+            "{{ data.team.lead }} {{ data.links[0].href }} {{ data.hours[1].day }} \
+             {{ data.nested.more.answer }}",
+            (),
+        )?;
+
+        assert_eq!(res, "Ada https://gentoo.org Tuesday 42");
+        Ok(())
+    }
+
+    #[test]
+    fn missing_dir() -> anyhow::Result<()> {
+        let data = load(Path::new("tests/fixtures/spec3/nothing"))?;
+
+        assert_eq!(data.len(), Some(0));
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+/// A problem found in a source file, displayed together with the offending line.
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    level: Level,
+    message: String,
+    file: PathBuf,
+    location: Option<Location>,
+    help: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Location {
+    line: usize,
+    column: usize,
+    text: String,
+    width: usize,
+}
+
+impl Diagnostic {
+    pub fn new(level: Level, message: impl Into<String>, file: &Path) -> Self {
+        Self {
+            level,
+            message: message.into(),
+            file: file.to_path_buf(),
+            location: None,
+            help: None,
+        }
+    }
+
+    /// Point the diagnostic at a byte range of `source`.
+    pub fn with_span(mut self, source: &str, span: Range<usize>) -> Self {
+        let start = span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let text = source[line_start..line_end].trim_end_matches('\r');
+
+        let line = source[..start].matches('\n').count() + 1;
+        let column = source[line_start..start].chars().count() + 1;
+        let end = span.end.clamp(start, line_end);
+        let width = source[start..end].chars().count().max(1);
+
+        self.location = Some(Location {
+            line,
+            column,
+            text: text.to_string(),
+            width,
+        });
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    pub fn level(&self) -> Level {
+        self.level
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.level {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        };
+        writeln!(f, "{}: {}", level, self.message)?;
+
+        match &self.location {
+            Some(loc) => {
+                let gutter = " ".repeat(loc.line.to_string().len());
+                writeln!(
+                    f,
+                    "{}--> {}:{}:{}",
+                    gutter,
+                    self.file.display(),
+                    loc.line,
+                    loc.column
+                )?;
+                writeln!(f, "{} |", gutter)?;
+                writeln!(f, "{} | {}", loc.line, loc.text)?;
+                write!(
+                    f,
+                    "{} | {}{}",
+                    gutter,
+                    " ".repeat(loc.column - 1),
+                    "^".repeat(loc.width)
+                )?;
+                if let Some(help) = &self.help {
+                    write!(f, "\n{} = help: {}", gutter, help)?;
+                }
+            }
+            None => {
+                write!(f, " --> {}", self.file.display())?;
+                if let Some(help) = &self.help {
+                    write!(f, "\n  = help: {}", help)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Find the candidate closest to `key`, if any is close enough to be a likely typo.
+pub fn suggest<'a>(key: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .map(|c| (strsim::damerau_levenshtein(key, c), *c))
+        .filter(|(d, c)| *d <= 2.max(c.len() / 3) && *d < c.len().max(key.len()))
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_with_span() {
+        let source = "[section]\nname = \"A\"\ndescr = \"B\"\n";
+        let diag = Diagnostic::new(
+            Level::Error,
+            "unknown key `descr`",
+            Path::new("a/section.toml"),
+        )
+        .with_span(source, 21..26)
+        .with_help("did you mean `desc`?");
+        let exp = r#"error: unknown key `descr`
+ --> a/section.toml:3:1
+  |
+3 | descr = "B"
+  | ^^^^^
+  = help: did you mean `desc`?"#;
+
+        assert_eq!(diag.to_string(), exp);
+    }
+
+    #[test]
+    fn display_without_span() {
+        let diag = Diagnostic::new(
+            Level::Warning,
+            "something is off",
+            Path::new("manifest.toml"),
+        );
+        let exp = "warning: something is off\n --> manifest.toml";
+
+        assert_eq!(diag.to_string(), exp);
+    }
+
+    #[test]
+    fn suggestions() {
+        let keys = ["name", "desc", "path"];
+
+        assert_eq!(suggest("descr", &keys), Some("desc"));
+        assert_eq!(suggest("nmae", &keys), Some("name"));
+        assert_eq!(suggest("title", &keys), None);
+    }
+}
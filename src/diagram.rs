@@ -0,0 +1,187 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::output::hash;
+
+/// Replace code blocks written in one of the languages of `commands` by the SVG image the
+/// command prints when given the block on its standard input. A dry run only uses the images
+/// already in `cache`.
+pub fn render(
+    html: &str,
+    commands: &BTreeMap<String, String>,
+    cache: &Path,
+    dry_run: bool,
+) -> anyhow::Result<String> {
+    const OPEN: &str = "<pre><code class=\"language-";
+    const CLOSE: &str = "</code></pre>";
+
+    let mut out = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find(OPEN) {
+        out.push_str(&rest[..start]);
+        let block = &rest[start..];
+        let Some(end) = block.find(CLOSE) else {
+            break;
+        };
+        let block = &block[..end + CLOSE.len()];
+        rest = &rest[start + block.len()..];
+
+        let info = &block[OPEN.len()..];
+        let (language, code) = info.split_once("\">").unwrap_or((info, ""));
+        let code = code.strip_suffix(CLOSE).unwrap_or(code);
+
+        match commands.get(language) {
+            Some(command) => match diagram(command, &unescape(code), cache, dry_run)? {
+                Some(svg) => out.push_str(&format!(
+                    "<div class=\"diagram {}\">{}</div>",
+                    language,
+                    svg.trim()
+                )),
+                None => out.push_str(block),
+            },
+            None => out.push_str(block),
+        }
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+fn diagram(
+    command: &str,
+    source: &str,
+    cache: &Path,
+    dry_run: bool,
+) -> anyhow::Result<Option<String>> {
+    let hash = hash(format!("{}\0{}", command, source).as_bytes());
+    let cached = cache.join(format!("{}.svg", hash));
+    if let Ok(svg) = fs::read_to_string(&cached) {
+        log::trace!("Using cached diagram {}", cached.display());
+        return Ok(Some(svg));
+    }
+    if dry_run {
+        log::trace!("Not running `{}` in a dry run", command);
+        return Ok(None);
+    }
+
+    let mut args = command.split_whitespace();
+    let Some(program) = args.next() else {
+        anyhow::bail!("empty diagram command");
+    };
+    log::info!("Running `{}` for a diagram", command);
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(c) => c,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            log::warn!(
+                "`{}` is not installed, leaving the diagram as code",
+                program
+            );
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    // written from another thread while the output is read, a command printing before it has
+    // read everything would wait for a full pipe forever otherwise
+    let stdin = child.stdin.take();
+    let (written, output) = std::thread::scope(|scope| {
+        let writer = scope.spawn(move || match stdin {
+            Some(mut stdin) => stdin.write_all(source.as_bytes()),
+            None => Ok(()),
+        });
+        let output = child.wait_with_output();
+        // can unwrap because writing does not panic
+        (writer.join().unwrap(), output)
+    });
+    let output = output?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "`{}` failed: {}",
+            command,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    written?;
+    let svg = String::from_utf8(output.stdout)?;
+    // drop the xml declaration and doctype, which are not allowed inside html
+    let svg = match svg.find("<svg") {
+        Some(i) => svg[i..].to_string(),
+        None => svg,
+    };
+
+    fs::create_dir_all(cache)?;
+    fs::write(&cached, &svg)?;
+    Ok(Some(svg))
+}
+
+fn unescape(code: &str) -> String {
+    code.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testing::TempDir;
+
+    #[test]
+    fn render_diagrams() -> anyhow::Result<()> {
+        let tmp = TempDir::new("diagrams");
+        let cache = tmp.join("cache");
+        let commands = BTreeMap::from([
+            ("svg".to_string(), "cat".to_string()),
+            ("missing".to_string(), "wgen-no-such-command".to_string()),
+        ]);
+        let html = "<pre><code class=\"language-svg\">&lt;?xml?&gt;&lt;svg&gt;&amp;&lt;/svg&gt;
+</code></pre>
+<pre><code class=\"language-missing\">a -&gt; b
+</code></pre>
+<pre><code class=\"language-rust\">fn main() {}
+</code></pre>";
+        let exp = "<div class=\"diagram svg\"><svg>&</svg></div>
+<pre><code class=\"language-missing\">a -&gt; b
+</code></pre>
+<pre><code class=\"language-rust\">fn main() {}
+</code></pre>";
+
+        // a dry run neither runs the commands nor fills the cache
+        assert_eq!(render(html, &commands, &cache, true)?, html);
+        assert!(!cache.exists());
+        assert_eq!(render(html, &commands, &cache, false)?, exp);
+        assert_eq!(cache.read_dir()?.count(), 1);
+
+        // served from the cache, without running anything
+        let commands = BTreeMap::from([("svg".to_string(), "cat".to_string())]);
+        fs::write(
+            cache.read_dir()?.next().unwrap()?.path(),
+            "<svg>cached</svg>",
+        )?;
+        let res = render(html, &commands, &cache, true)?;
+        assert!(res.starts_with("<div class=\"diagram svg\"><svg>cached</svg></div>"));
+        Ok(())
+    }
+
+    #[test]
+    fn large_diagram() -> anyhow::Result<()> {
+        let tmp = TempDir::new("large-diagram");
+        let source = format!("<svg>{}</svg>", "a".repeat(1 << 20));
+
+        let svg = diagram("cat", &source, &tmp, false)?;
+        assert_eq!(svg, Some(source));
+        Ok(())
+    }
+}
@@ -2,15 +2,33 @@
 // Distributed under the terms of the GNU General Public License v3 or later
 
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
-use crate::spec::*;
+use crate::data;
+use crate::render;
+use crate::spec::{self, *};
 use crate::tree;
 
+/// Settings affecting how the spec files are turned into a [`tree::Tree`].
+#[derive(Debug, Default)]
+pub struct Options {
+    pub strict: bool,
+    pub auto_include: bool,
+    pub drafts: bool,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Structure {
     pub root: PathBuf,
     sections: Vec<Section>,
+    markdown: Vec<PathBuf>,
+    skipped: Vec<Skipped>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skipped {
+    pub path: PathBuf,
+    pub reason: String,
 }
 
 #[derive(Debug, PartialEq)]
@@ -25,123 +43,266 @@ struct Subsection(PathBuf);
 impl Structure {
     pub fn collect_from(root: &Path) -> anyhow::Result<Self> {
         match collect_structure(root) {
-            Ok(sections) => Ok(Self {
-                root: root.to_path_buf(),
-                sections,
-            }),
+            Ok(structure) => Ok(structure),
             Err(e) => {
                 anyhow::bail!("fetching the tree failed: {}", e)
             }
         }
     }
 
-    pub fn into_tree(self) -> anyhow::Result<tree::Tree> {
+    /// The manifest and the spec files of the sections and subsections found.
+    pub fn specs(&self) -> Vec<PathBuf> {
+        let mut specs = vec![self.root.join("manifest.toml")];
+        for section in &self.sections {
+            specs.push(section.spec.clone());
+            specs.extend(section.subsections.iter().map(|s| s.0.clone()));
+        }
+        specs
+    }
+
+    pub fn into_tree(self, opts: &Options) -> anyhow::Result<tree::Tree> {
+        Ok(self.into_tree_and_skipped(opts)?.0)
+    }
+
+    /// Like [`Structure::into_tree`], also returning the skipped directories, including the
+    /// sections and subsections whose spec could not be read.
+    pub fn into_tree_and_skipped(
+        mut self,
+        opts: &Options,
+    ) -> anyhow::Result<(tree::Tree, Vec<Skipped>)> {
         let spec = self.root.join("manifest.toml");
         log::info!("Reading {}", spec.display());
-        let spec = fs::read_to_string(spec)?;
-        let spec: ManifestSpec = toml::from_str(&spec)?;
-
-        let sections = self
-            .sections
-            .into_iter()
-            .flat_map(|s| {
-                let path = s.spec.clone();
-                match s.read_spec(&self.root) {
-                    Ok(sub) => Ok(sub),
-                    Err(e) => {
-                        log::warn!("Could not read the spec {}: {}", path.display(), e);
-                        Err(())
-                    }
-                }
-            });
-        
-        Ok(tree::Tree::from_spec(spec, self.root.clone(), sections))
+        let mut spec: ManifestSpec = spec::read(&spec, opts.strict)?;
+
+        if opts.auto_include {
+            let main_page = self.root.join(&spec.main_page);
+            include_orphans(&mut spec.pages, &self.root, &self.markdown, &[main_page]);
+        }
+
+        let collected = self.skipped.len();
+        let mut sections = Vec::new();
+        for s in std::mem::take(&mut self.sections) {
+            let path = s.spec.clone();
+            match s.read_spec(&self.root, opts, &self.markdown, &mut self.skipped) {
+                Ok(sec) => sections.push(sec),
+                Err(e) if opts.strict => return Err(e),
+                Err(e) => unreadable(&path, e, &mut self.skipped),
+            }
+        }
+
+        let mut tree = tree::Tree::from_spec(spec, self.root.clone(), sections.into_iter());
+        // which of them are included is only known when building
+        let orphans = orphans(&self.markdown, &tree).into_iter().map(Path::to_path_buf);
+        tree.set_orphans(orphans.collect());
+        // unreadable specs were reported as they were read
+        for skipped in &self.skipped[..collected] {
+            log::warn!("{} was skipped: {}", skipped.path.display(), skipped.reason);
+        }
+
+        if !opts.drafts {
+            let dropped = tree.drop_drafts();
+            if dropped > 0 {
+                log::info!("Leaving out {} draft(s), pass --drafts to build them", dropped);
+            }
+        }
+
+        Ok((tree, self.skipped))
+    }
+}
+
+/// Markdown files which are not the source of any page in `tree`.
+pub fn orphans<'a>(markdown: &'a [PathBuf], tree: &tree::Tree) -> Vec<&'a Path> {
+    let indexes = tree
+        .sections()
+        .filter_map(|s| s.index())
+        .chain(tree.subsections().filter_map(|s| s.index()));
+    let listed: Vec<PathBuf> = tree
+        .pages()
+        .chain(std::iter::once(tree.main_page()))
+        .map(|p| p.file())
+        .chain(indexes)
+        .map(normalize)
+        .collect();
+
+    markdown
+        .iter()
+        .filter(|f| !listed.contains(&normalize(f)))
+        .map(|f| f.as_path())
+        .collect()
+}
+
+fn include_orphans(
+    pages: &mut Vec<PageSpec>,
+    location: &Path,
+    markdown: &[PathBuf],
+    exclude: &[PathBuf],
+) {
+    let listed: Vec<PathBuf> = pages
+        .iter()
+        .map(|p| normalize(&location.join(&p.path)))
+        .collect();
+
+    for file in markdown {
+        let in_location = file.parent() == Some(location);
+        if !in_location || listed.contains(&normalize(file)) || exclude.contains(file) {
+            continue;
+        }
+        let Some(name) = file.file_name() else {
+            continue;
+        };
+
+        log::info!("Including unlisted file {}", file.display());
+        pages.push(PageSpec {
+            name: page_title(file),
+            desc: "".to_string(),
+            path: name.to_string_lossy().to_string(),
+            draft: false,
+            unlisted: false,
+            tags: vec![],
+            categories: vec![],
+            template: None,
+            bibliography: None,
+        });
+    }
+}
+
+/// `path` with `.` components removed and `..` ones applied, without touching the filesystem.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir if normal.file_name().is_some() => {
+                normal.pop();
+            }
+            c => normal.push(c),
+        }
     }
+    normal
+}
+
+fn page_title(file: &Path) -> String {
+    let heading = fs::read_to_string(file).ok().and_then(|content| {
+        content
+            .lines()
+            .find_map(|l| l.strip_prefix("# ").map(|h| h.trim().to_string()))
+    });
+
+    heading.unwrap_or_else(|| {
+        file.file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    })
 }
 
 impl Section {
-    fn read_spec(self, root: &Path) -> anyhow::Result<tree::Section> {
+    fn read_spec(
+        self,
+        root: &Path,
+        opts: &Options,
+        markdown: &[PathBuf],
+        skipped: &mut Vec<Skipped>,
+    ) -> anyhow::Result<tree::Section> {
         log::info!("Reading section specfile {}", self.spec.display());
-        let spec = fs::read_to_string(&self.spec)?;
-        let spec: SectionSpec = toml::from_str(&spec)?;
-
-        let subsections = self
-            .subsections
-            .into_iter()
-            .flat_map(|s| {
-                let path = s.0.clone();
-                match s.read_spec(root) {
-                    Ok(sub) => Ok(sub),
-                    Err(e) => {
-                        log::warn!("Could not read the spec {}: {}", path.display(), e);
-                        Err(())
-                    }
-                }
-            });
-
+        let mut spec: SectionSpec = spec::read(&self.spec, opts.strict)?;
         let location = self.spec.parent().unwrap_or(Path::new(""));
-        Ok(tree::Section::from_spec(spec, subsections, location, root))
+
+        if opts.auto_include {
+            let index: Vec<PathBuf> =
+                spec.section.index.iter().map(|i| location.join(i)).collect();
+            include_orphans(&mut spec.pages, location, markdown, &index);
+        }
+
+        let mut subsections = Vec::new();
+        for s in self.subsections {
+            let path = s.0.clone();
+            match s.read_spec(root, opts, markdown) {
+                Ok(sub) => subsections.push(sub),
+                Err(e) if opts.strict => return Err(e),
+                Err(e) => unreadable(&path, e, skipped),
+            }
+        }
+
+        Ok(tree::Section::from_spec(spec, subsections.into_iter(), location, root))
     }
 }
 
 impl Subsection {
-    fn read_spec(self, root: &Path) -> anyhow::Result<tree::Subsection> {
+    fn read_spec(
+        self,
+        root: &Path,
+        opts: &Options,
+        markdown: &[PathBuf],
+    ) -> anyhow::Result<tree::Subsection> {
         log::info!("Reading subsection specfile {}", self.0.display());
-        let spec = fs::read_to_string(&self.0)?;
-        let spec: SubsectionSpec = toml::from_str(&spec)?;
+        let mut spec: SubsectionSpec = spec::read(&self.0, opts.strict)?;
         let location = self.0.parent().unwrap_or(Path::new(""));
+
+        if opts.auto_include {
+            let index: Vec<PathBuf> =
+                spec.subsection.index.iter().map(|i| location.join(i)).collect();
+            include_orphans(&mut spec.pages, location, markdown, &index);
+        }
+
         Ok(tree::Subsection::from_spec(spec, location, root))
     }
 }
 
-fn collect_structure(root: &Path) -> anyhow::Result<Vec<Section>> {
+fn collect_structure(root: &Path) -> anyhow::Result<Structure> {
     log::info!("Collecting entries from root at {}", root.display());
 
     let spec = root.join("manifest.toml");
     if spec.try_exists()? && spec.is_file() {
+        let (dirs, mut markdown) = entries(root);
         let mut sections = Vec::new();
-        if let Ok(read_dir) = root.read_dir() {
-            for entry in read_dir {
-                match entry {
-                    Ok(entry) => {
-                        let path = entry.path();
-                        if path.is_dir() {
-                            match collect_dir(&path) {
-                                Ok(sec) => sections.push(sec),
-                                Err(e) => log::warn!("Skipping {}: {}", path.display(), e),
-                            }
-                        }
-                    }
-                    Err(e) => log::warn!("Skipping a file in {}: {}", root.display(), e),
+        let mut skipped = Vec::new();
+        for path in dirs {
+            if let Some(reason) = reserved(&path) {
+                if path.join("section.toml").is_file() {
+                    log::warn!(
+                        "{} has a section.toml, but is not a section: it is {}",
+                        path.display(),
+                        reason
+                    );
                 }
+                skipped.push(Skipped {
+                    path,
+                    reason: reason.to_string(),
+                });
+                continue;
+            }
+            match collect_dir(&path, &mut markdown, &mut skipped) {
+                Ok(sec) => sections.push(sec),
+                Err(e) => skip(path, e, &mut skipped),
             }
         }
-        Ok(sections)
+        Ok(Structure {
+            root: root.to_path_buf(),
+            sections,
+            markdown,
+            skipped,
+        })
     } else {
         anyhow::bail!("no manifest.toml found in the root, aborting...");
     }
 }
 
-fn collect_dir(root: &Path) -> anyhow::Result<Section> {
+fn collect_dir(
+    root: &Path,
+    markdown: &mut Vec<PathBuf>,
+    skipped: &mut Vec<Skipped>,
+) -> anyhow::Result<Section> {
     log::info!("Entering directory {}", root.display());
 
     let spec = root.join("section.toml");
     if spec.try_exists()? && spec.is_file() {
+        let (dirs, files) = entries(root);
+        markdown.extend(files);
         let mut subsections = Vec::new();
-        if let Ok(read_dir) = root.read_dir() {
-            for entry in read_dir {
-                match entry {
-                    Ok(entry) => {
-                        let path = entry.path();
-                        if path.is_dir() {
-                            match collect_subdir(&path) {
-                                Ok(sub) => subsections.push(sub),
-                                Err(e) => log::warn!("Skipping {}: {}", path.display(), e),
-                            }
-                        }
-                    }
-                    Err(e) => log::warn!("Skipping a file in {}: {}", root.display(), e),
-                }
+        for path in dirs {
+            match collect_subdir(&path, markdown) {
+                Ok(sub) => subsections.push(sub),
+                Err(e) => skip(path, e, skipped),
             }
         }
         Ok(Section { spec, subsections })
@@ -150,11 +311,68 @@ fn collect_dir(root: &Path) -> anyhow::Result<Section> {
     }
 }
 
-fn collect_subdir(root: &Path) -> anyhow::Result<Subsection> {
+/// Why `dir` in the root is never a section, if it is not.
+fn reserved(dir: &Path) -> Option<&'static str> {
+    match dir.file_name()? {
+        n if n == render::TEMPLATE_DIR => Some("reserved for templates"),
+        n if n == data::DATA_DIR => Some("reserved for data files"),
+        _ => None,
+    }
+}
+
+fn unreadable(path: &Path, e: anyhow::Error, skipped: &mut Vec<Skipped>) {
+    log::warn!("Could not read the spec {}:\n{}", path.display(), e);
+    // the first line of a diagnostic is its message
+    let message = e.to_string();
+    let message = message.lines().next().unwrap_or_default();
+    skipped.push(Skipped {
+        path: path.parent().unwrap_or(Path::new("")).to_path_buf(),
+        reason: format!(
+            "{} could not be read: {}",
+            path.file_name().unwrap_or_default().to_string_lossy(),
+            message.trim_start_matches("error: ")
+        ),
+    });
+}
+
+fn skip(path: PathBuf, reason: anyhow::Error, skipped: &mut Vec<Skipped>) {
+    log::info!("Skipping {}: {}", path.display(), reason);
+    skipped.push(Skipped {
+        path,
+        reason: reason.to_string(),
+    });
+}
+
+fn entries(root: &Path) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let mut dirs = Vec::new();
+    let mut markdown = Vec::new();
+    if let Ok(read_dir) = root.read_dir() {
+        for entry in read_dir {
+            match entry {
+                Ok(entry) => {
+                    let path = entry.path();
+                    let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                    if path.is_dir() && !hidden {
+                        dirs.push(path);
+                    } else if path.is_file() && path.extension().is_some_and(|e| e == "md") {
+                        markdown.push(path);
+                    }
+                }
+                Err(e) => log::warn!("Skipping a file in {}: {}", root.display(), e),
+            }
+        }
+    }
+    dirs.sort();
+    markdown.sort();
+    (dirs, markdown)
+}
+
+fn collect_subdir(root: &Path, markdown: &mut Vec<PathBuf>) -> anyhow::Result<Subsection> {
     log::info!("Entering subdirectory {}", root.display());
 
     let spec = root.join("subsection.toml");
     if spec.try_exists()? && spec.is_file() {
+        markdown.extend(entries(root).1);
         Ok(Subsection(spec))
     } else {
         anyhow::bail!("subsection.toml not found or is not a file")
@@ -166,6 +384,8 @@ mod tests {
     use super::*;
     use std::path::{Path, PathBuf};
 
+    use crate::testing::TempDir;
+
     #[test]
     fn collect_spec1() -> anyhow::Result<()> {
         let structure = Structure::collect_from(std::path::Path::new("spec1"))?;
@@ -181,6 +401,16 @@ mod tests {
                     subsections: vec![],
                 },
             ],
+            markdown: vec![
+                PathBuf::from("spec1/1.md"),
+                PathBuf::from("spec1/2.md"),
+                PathBuf::from("spec1/d1/1.md"),
+                PathBuf::from("spec1/d1/2.md"),
+                PathBuf::from("spec1/d1/s1/1.md"),
+                PathBuf::from("spec1/d1/s1/2.md"),
+                PathBuf::from("spec1/d2/1.md"),
+            ],
+            skipped: vec![],
         };
 
         assert_eq!(structure, expect);
@@ -205,6 +435,21 @@ mod tests {
                     subsections: vec![Subsection(PathBuf::from("spec2/d2/s1/subsection.toml"))],
                 },
             ],
+            markdown: vec![
+                PathBuf::from("spec2/1.md"),
+                PathBuf::from("spec2/2.md"),
+                PathBuf::from("spec2/index.md"),
+                PathBuf::from("spec2/d1/1.md"),
+                PathBuf::from("spec2/d1/2.md"),
+                PathBuf::from("spec2/d1/s1/1.md"),
+                PathBuf::from("spec2/d1/s1/2.md"),
+                PathBuf::from("spec2/d1/s2/1.md"),
+                PathBuf::from("spec2/d1/s2/2.md"),
+                PathBuf::from("spec2/d2/1.md"),
+                PathBuf::from("spec2/d2/2.md"),
+                PathBuf::from("spec2/d2/s1/1.md"),
+            ],
+            skipped: vec![],
         };
 
         assert_eq!(structure, expect);
@@ -214,7 +459,7 @@ mod tests {
     #[test]
     fn subsection_read_spec1() -> anyhow::Result<()> {
         let subsection = Subsection(PathBuf::from("spec1/d1/s1/subsection.toml"));
-        let subsection = subsection.read_spec(Path::new("spec1"))?;
+        let subsection = subsection.read_spec(Path::new("spec1"), &Options::default(), &[])?;
         let expect = tree::Subsection::subsection_read_spec1_expected();
 
         assert_eq!(subsection, expect);
@@ -224,7 +469,7 @@ mod tests {
     #[test]
     fn subsection_read_spec2() -> anyhow::Result<()> {
         let subsection = Subsection(PathBuf::from("spec2/d2/s1/subsection.toml"));
-        let subsection = subsection.read_spec(Path::new("spec2"))?;
+        let subsection = subsection.read_spec(Path::new("spec2"), &Options::default(), &[])?;
         let expect = tree::Subsection::subsection_read_spec2_expected();
 
         assert_eq!(subsection, expect);
@@ -240,10 +485,13 @@ mod tests {
                 Subsection(PathBuf::from("spec2/d1/s2/subsection.toml")),
             ],
         };
-        let section = section.read_spec(Path::new("spec2"))?;
+        let mut skipped = Vec::new();
+        let section =
+            section.read_spec(Path::new("spec2"), &Options::default(), &[], &mut skipped)?;
         let expect = tree::Section::section_read_spec_expected();
 
         assert_eq!(section, expect);
+        assert!(skipped.is_empty());
         Ok(())
     }
 
@@ -264,12 +512,103 @@ mod tests {
                     subsections: vec![Subsection(PathBuf::from("spec2/d2/s1/subsection.toml"))],
                 },
             ],
+            markdown: vec![],
+            skipped: vec![],
         };
 
-        let tree = structure.into_tree()?;
+        let tree = structure.into_tree(&Options::default())?;
         let expect = tree::Tree::structure_into_tree_expect();
 
         assert_eq!(tree, expect);
         Ok(())
     }
+
+    #[test]
+    fn orphaned_files() -> anyhow::Result<()> {
+        let structure = Structure::collect_from(Path::new("tests/fixtures/spec3"))?;
+        let expect = vec![
+            Skipped {
+                path: PathBuf::from("tests/fixtures/spec3/assets"),
+                reason: "section.toml not found or is not a file".to_string(),
+            },
+            Skipped {
+                path: PathBuf::from("tests/fixtures/spec3/data"),
+                reason: "reserved for data files".to_string(),
+            },
+            Skipped {
+                path: PathBuf::from("tests/fixtures/spec3/templates"),
+                reason: "reserved for templates".to_string(),
+            },
+        ];
+        assert_eq!(structure.skipped, expect);
+
+        // drafts are not orphans, but would be left out of the tree
+        let opts = Options {
+            drafts: true,
+            ..Default::default()
+        };
+        let markdown = structure.markdown.clone();
+        let tree = structure.into_tree(&opts)?;
+        assert_eq!(orphans(&markdown, &tree), vec![Path::new("tests/fixtures/spec3/orphan.md")]);
+        Ok(())
+    }
+
+    #[test]
+    fn normalize_paths() {
+        assert_eq!(normalize(Path::new("spec1/d1/../1.md")), Path::new("spec1/1.md"));
+        assert_eq!(normalize(Path::new("./spec1/./d1/2.md")), Path::new("spec1/d1/2.md"));
+        assert_eq!(normalize(Path::new("../spec1/../../a.md")), Path::new("../../a.md"));
+    }
+
+    #[test]
+    fn auto_include() -> anyhow::Result<()> {
+        let opts = Options {
+            auto_include: true,
+            drafts: true,
+            ..Default::default()
+        };
+        let structure = Structure::collect_from(Path::new("tests/fixtures/spec3"))?;
+        let markdown = structure.markdown.clone();
+        let tree = structure.into_tree(&opts)?;
+        let pages: Vec<&str> = tree.pages().map(|p| p.name()).collect();
+
+        assert_eq!(pages, vec!["Listed", "Work in progress", "Forgotten page"]);
+        assert!(orphans(&markdown, &tree).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn drafts() -> anyhow::Result<()> {
+        let structure = Structure::collect_from(Path::new("tests/fixtures/spec3"))?;
+        let tree = structure.into_tree(&Options::default())?;
+        let pages: Vec<&str> = tree.pages().map(|p| p.name()).collect();
+        assert_eq!(pages, vec!["Listed"]);
+
+        let opts = Options {
+            drafts: true,
+            ..Default::default()
+        };
+        let structure = Structure::collect_from(Path::new("tests/fixtures/spec3"))?;
+        let tree = structure.into_tree(&opts)?;
+        let pages: Vec<&str> = tree.pages().map(|p| p.name()).collect();
+        assert_eq!(pages, vec!["Listed", "Work in progress"]);
+        Ok(())
+    }
+
+    #[test]
+    fn unreadable_spec() -> anyhow::Result<()> {
+        let root = TempDir::new("unreadable");
+        fs::create_dir_all(root.join("broken"))?;
+        fs::write(root.join("manifest.toml"), "title = \"T\"\nmain_page = \"a.md\"\npage = []\n")?;
+        fs::write(root.join("broken/section.toml"), "[section")?;
+
+        let structure = Structure::collect_from(&root)?;
+        let (tree, skipped) = structure.into_tree_and_skipped(&Options::default())?;
+
+        assert_eq!(tree.sections().count(), 0);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].path, root.join("broken"));
+        assert!(skipped[0].reason.starts_with("section.toml could not be read: "));
+        Ok(())
+    }
 }
@@ -0,0 +1,394 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use markdown::ParseOptions;
+use markdown::mdast::Node;
+
+pub type Edit = (Range<usize>, String);
+
+/// A term and the lines of each of its definitions.
+type Definition<'a> = (&'a str, Vec<Vec<&'a str>>);
+
+/// Private use characters which are neither in `source` nor written as a character reference
+/// in it, so the html converted from it only has them where they were put in as placeholders.
+pub fn unused_chars(source: &str) -> impl Iterator<Item = char> + use<> {
+    let mut used: HashSet<char> = source.chars().collect();
+    for (i, _) in source.match_indices("&#") {
+        let rest = &source[i + 2..];
+        let (digits, radix) = match rest.strip_prefix(['x', 'X']) {
+            Some(hex) => (hex, 16),
+            None => (rest, 10),
+        };
+        let end = digits
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(digits.len());
+        if let Some(c) = u32::from_str_radix(&digits[..end], radix)
+            .ok()
+            .and_then(char::from_u32)
+        {
+            used.insert(c);
+        }
+    }
+    ('\u{E000}'..='\u{F8FF}').filter(move |c| !used.contains(c))
+}
+
+/// Rewrite definition lists into placeholder paragraphs with `mark`, which
+/// [`finish_definition_lists`] turns into html after conversion.
+pub fn definition_lists(
+    source: &str,
+    options: &ParseOptions,
+    mark: char,
+) -> anyhow::Result<String> {
+    let root = parse(source, options)?;
+    let mut edits = Vec::new();
+    find_definitions(&root, source, 0, mark, &mut edits);
+    Ok(apply(source, edits))
+}
+
+pub fn finish_definition_lists(html: &str, mark: char) -> String {
+    let mut html = html
+        .replace(&format!("<p>{}dl{}</p>", mark, mark), "<dl>")
+        .replace(&format!("<p>{}/dl{}</p>", mark, mark), "</dl>");
+    for tag in ["dt", "dd"] {
+        let (open, close) = (
+            format!("{}{}{}", mark, tag, mark),
+            format!("{}/{}{}", mark, tag, mark),
+        );
+        html = html.replace(&format!("<p>{}", open), &format!("<{}>", tag));
+        html = html.replace(&format!("{}</p>", close), &format!("</{}>", tag));
+    }
+    html
+}
+
+/// Take `{#id .class}` off the end of headings, leaving a placeholder with `mark` at their start
+/// which [`finish_heading_attributes`] turns into the attributes of the heading element.
+pub fn heading_attributes(
+    source: &str,
+    options: &ParseOptions,
+    mark: char,
+) -> anyhow::Result<(String, Vec<(u8, String)>)> {
+    let root = parse(source, options)?;
+    let mut edits = Vec::new();
+    let mut headings = Vec::new();
+    find_headings(&root, source, mark, &mut edits, &mut headings);
+    Ok((apply(source, edits), headings))
+}
+
+pub fn finish_heading_attributes(html: &str, headings: &[(u8, String)], mark: char) -> String {
+    let mut html = html.to_string();
+    for (i, (level, attributes)) in headings.iter().enumerate() {
+        let placeholder = format!("<h{}>{}h{}{}", level, mark, i, mark);
+        html = html.replace(&placeholder, &format!("<h{}{}>", level, attributes));
+    }
+    html
+}
+
+fn parse(source: &str, options: &ParseOptions) -> anyhow::Result<Node> {
+    match markdown::to_mdast(source, options) {
+        Ok(root) => Ok(root),
+        Err(e) => anyhow::bail!("{}", e),
+    }
+}
+
+fn find_definitions(node: &Node, source: &str, quotes: usize, mark: char, edits: &mut Vec<Edit>) {
+    let Some(children) = node.children() else {
+        return;
+    };
+    let quotes = quotes + usize::from(matches!(node, Node::Blockquote(_)));
+
+    // paragraphs next to each other make up one list
+    let mut list: Option<(Range<usize>, Vec<Definition>)> = None;
+    for child in children {
+        let found = match (child, child.position()) {
+            (Node::Paragraph(_), Some(p)) => {
+                let range = p.start.offset..p.end.offset;
+                definition(&source[range.clone()], quotes).map(|d| (range, d))
+            }
+            _ => None,
+        };
+        match (found, &mut list) {
+            (Some((range, d)), Some((r, definitions))) => {
+                r.end = range.end;
+                definitions.push(d);
+            }
+            (Some((range, d)), None) => list = Some((range, vec![d])),
+            (None, _) => {
+                if let Some((r, definitions)) = list.take() {
+                    let placeholders = placeholders(source, r.start, mark, &definitions);
+                    edits.push((r, placeholders));
+                }
+                find_definitions(child, source, quotes, mark, edits);
+            }
+        }
+    }
+    if let Some((r, definitions)) = list {
+        let placeholders = placeholders(source, r.start, mark, &definitions);
+        edits.push((r, placeholders));
+    }
+}
+
+/// The term and definitions in the source of a paragraph, if its second line starts with `: `.
+fn definition(paragraph: &str, quotes: usize) -> Option<Definition<'_>> {
+    let mut lines = paragraph.lines();
+    let term = lines.next()?.trim();
+    let mut definitions: Vec<Vec<&str>> = Vec::new();
+    for line in lines.map(|l| unquote(l, quotes)) {
+        match line.strip_prefix(": ") {
+            Some(d) => definitions.push(vec![d.trim()]),
+            None => definitions.last_mut()?.push(line),
+        }
+    }
+    (!definitions.is_empty()).then_some((term, definitions))
+}
+
+/// Strip the `>` markers of `quotes` nested blockquotes from a line.
+fn unquote(line: &str, quotes: usize) -> &str {
+    let mut line = line;
+    for _ in 0..quotes {
+        let trimmed = line.trim_start();
+        line = trimmed.strip_prefix('>').unwrap_or(trimmed);
+    }
+    line.trim()
+}
+
+/// Paragraphs standing in for a definition list starting at `start`, continuing the blockquotes
+/// and list items it is in.
+fn placeholders(source: &str, start: usize, mark: char, definitions: &[Definition]) -> String {
+    let prefix = container_prefix(source, start);
+    let blank = prefix.trim_end();
+    let paragraph = |tag: &str, text: &str| {
+        format!(
+            "\n{}\n{}{}{}{}{}{}/{}{}",
+            blank, prefix, mark, tag, mark, text, mark, tag, mark
+        )
+    };
+
+    let mut out = format!("{}dl{}", mark, mark);
+    for (term, lines) in definitions {
+        out.push_str(&paragraph("dt", term));
+        for lines in lines {
+            out.push_str(&paragraph("dd", &lines.join(&format!("\n{}", prefix))));
+        }
+    }
+    out.push_str(&format!("\n{}\n{}{}/dl{}", blank, prefix, mark, mark));
+    out
+}
+
+/// What continues the blockquotes and list items a block starting at `start` is in, on the
+/// lines after its first one.
+pub fn container_prefix(source: &str, start: usize) -> String {
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    source[line_start..start]
+        .chars()
+        .map(|c| match c {
+            '>' => c,
+            c if c.is_whitespace() => c,
+            _ => ' ',
+        })
+        .collect()
+}
+
+fn find_headings(
+    node: &Node,
+    source: &str,
+    mark: char,
+    edits: &mut Vec<Edit>,
+    headings: &mut Vec<(u8, String)>,
+) {
+    if let Node::Heading(heading) = node
+        && let (Some(first), Some(Node::Text(text))) =
+            (heading.children.first(), heading.children.last())
+        && let (Some(first), Some(last)) = (first.position(), text.position.as_ref())
+    {
+        let raw = &source[last.start.offset..last.end.offset];
+        if let Some((rest, attributes)) = split_attributes(raw) {
+            let start = first.start.offset;
+            let marker = format!("{}h{}{}", mark, headings.len(), mark);
+            edits.push((start..start, marker));
+            edits.push((
+                last.start.offset + rest.len()..last.end.offset,
+                String::new(),
+            ));
+            headings.push((heading.depth, attributes));
+        }
+        return;
+    }
+
+    for child in node.children().into_iter().flatten() {
+        find_headings(child, source, mark, edits, headings);
+    }
+}
+
+fn split_attributes(text: &str) -> Option<(&str, String)> {
+    let inner = text.trim_end().strip_suffix('}')?;
+    let open = inner.rfind('{')?;
+    // `\{` is a literal brace
+    let escapes = inner[..open]
+        .chars()
+        .rev()
+        .take_while(|&c| c == '\\')
+        .count();
+    if escapes % 2 == 1 {
+        return None;
+    }
+    let (text, inner) = (inner[..open].trim_end(), &inner[open + 1..]);
+
+    let mut id = None;
+    let mut classes = Vec::new();
+    for part in inner.split_whitespace() {
+        let valid = |s: &str| {
+            !s.is_empty()
+                && s.chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        };
+        if let Some(i) = part.strip_prefix('#').filter(|i| valid(i)) {
+            id = Some(i);
+        } else if let Some(c) = part.strip_prefix('.').filter(|c| valid(c)) {
+            classes.push(c);
+        } else {
+            return None;
+        }
+    }
+    if id.is_none() && classes.is_empty() {
+        return None;
+    }
+
+    let mut attributes = String::new();
+    if let Some(id) = id {
+        attributes.push_str(&format!(" id=\"{}\"", id));
+    }
+    if !classes.is_empty() {
+        attributes.push_str(&format!(" class=\"{}\"", classes.join(" ")));
+    }
+    Some((text, attributes))
+}
+
+/// Replace the ranges of `source` in `edits`, which do not overlap.
+pub fn apply(source: &str, mut edits: Vec<Edit>) -> String {
+    edits.sort_by_key(|(r, _)| (r.start, r.end));
+    let mut out = String::new();
+    let mut last = 0;
+    for (range, replacement) in edits {
+        out.push_str(&source[last..range.start]);
+        out.push_str(&replacement);
+        last = range.end;
+    }
+    out.push_str(&source[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(source: &str) -> String {
+        let options = markdown::Options {
+            compile: markdown::CompileOptions {
+                allow_dangerous_html: true,
+                ..markdown::CompileOptions::gfm()
+            },
+            ..markdown::Options::gfm()
+        };
+        let mark = unused_chars(source).next().unwrap();
+        let source = definition_lists(source, &options.parse, mark).unwrap();
+        let (source, headings) = heading_attributes(&source, &options.parse, mark).unwrap();
+        let html = markdown::to_html_with_options(&source, &options).unwrap();
+        let html = finish_definition_lists(&html, mark);
+        finish_heading_attributes(&html, &headings, mark)
+    }
+
+    #[test]
+    fn definitions() {
+        let res = convert(
+            "Intro
+
+Apple
+: A *fruit*
+growing on trees
+: A company
+
+Rust
+: A language
+
+End
+",
+        );
+        let exp = "<p>Intro</p>
+<dl>
+<dt>Apple</dt>
+<dd>A <em>fruit</em>
+growing on trees</dd>
+<dd>A company</dd>
+<dt>Rust</dt>
+<dd>A language</dd>
+</dl>
+<p>End</p>
+";
+
+        assert_eq!(res, exp);
+    }
+
+    #[test]
+    fn definitions_need_a_blank_line() {
+        let source = "Some text\nTerm\n: not a definition\n";
+
+        assert_eq!(
+            definition_lists(source, &ParseOptions::gfm(), '\u{E000}').unwrap(),
+            source
+        );
+    }
+
+    #[test]
+    fn nested_definitions() {
+        let res =
+            convert("> Apple\n> : A fruit\n\n- Rust\n  : A language\n\n```\nTerm\n: code\n```\n");
+        let exp = "<blockquote>
+<dl>
+<dt>Apple</dt>
+<dd>A fruit</dd>
+</dl>
+</blockquote>
+<ul>
+<li>
+<dl>
+<dt>Rust</dt>
+<dd>A language</dd>
+</dl>
+</li>
+</ul>
+<pre><code>Term
+: code
+</code></pre>
+";
+
+        assert_eq!(res, exp);
+    }
+
+    #[test]
+    fn placeholders_in_source() {
+        let res = convert("\u{E000}dl\u{E000}\n\n## \u{E000}h0\u{E000} {.a}\n");
+        let exp = "<p>\u{E000}dl\u{E000}</p>\n<h2 class=\"a\">\u{E000}h0\u{E000}</h2>\n";
+
+        assert_eq!(res, exp);
+    }
+
+    #[test]
+    fn headings() {
+        let res = convert(
+            "# Title {#top .big .red}\n\n## Set {1, 2}\n\n> ## a <code>x</code> *b* {.c}\n\n\
+             ## Brace \\{.d}\n",
+        );
+        let exp = "<h1 id=\"top\" class=\"big red\">Title</h1>
+<h2>Set {1, 2}</h2>
+<blockquote>
+<h2 class=\"c\">a <code>x</code> <em>b</em></h2>
+</blockquote>
+<h2>Brace {.d}</h2>
+";
+
+        assert_eq!(res, exp);
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+use crate::spec::{Keys, ManifestSpec, SectionSpec, SubsectionSpec};
+
+/// Keys of the table under a path in a spec file, in the order they are written in.
+type Order = fn(&[&str]) -> &'static [&'static str];
+
+/// Format the spec files `specs`, or with `check` only report the ones which are not formatted.
+pub fn format_files(specs: &[PathBuf], check: bool) -> anyhow::Result<usize> {
+    let mut changed = 0;
+    for spec in specs {
+        let source = fs::read_to_string(spec)?;
+        let formatted = match format(&source, order(spec)) {
+            Ok(f) => f,
+            Err(e) => anyhow::bail!("could not parse {}: {}", spec.display(), e),
+        };
+        if formatted == source {
+            continue;
+        }
+
+        changed += 1;
+        if check {
+            log::warn!("{} is not formatted", spec.display());
+        } else {
+            log::info!("Formatting {}", spec.display());
+            fs::write(spec, formatted)?;
+        }
+    }
+    Ok(changed)
+}
+
+fn order(spec: &Path) -> Order {
+    match spec.file_name().and_then(|f| f.to_str()) {
+        Some("section.toml") => SectionSpec::keys,
+        Some("subsection.toml") => SubsectionSpec::keys,
+        _ => ManifestSpec::keys,
+    }
+}
+
+/// Format a spec file: keys in the order of the [`Keys`] of the spec, values written the
+/// default way, no indentation and at most one blank line in a row, one before every table.
+/// Comments are kept with the key or table below them.
+pub fn format(source: &str, order: Order) -> anyhow::Result<String> {
+    let mut doc: DocumentMut = source.parse()?;
+    let mut position = 0;
+    format_table(doc.as_table_mut(), &[], order, &mut position);
+
+    let trailing = tidy(doc.trailing().as_str().unwrap_or_default());
+    doc.set_trailing(trailing);
+
+    let out = doc.to_string();
+    Ok(out.trim_start_matches('\n').trim_end().to_string() + "\n")
+}
+
+fn format_table(table: &mut Table, path: &[&str], order: Order, position: &mut isize) {
+    let keys = order(path);
+    // unknown keys go last, in the order they were written in
+    let rank = |key: &str| keys.iter().position(|k| *k == key).unwrap_or(keys.len());
+    table.sort_values_by(|k1, _, k2, _| rank(k1.get()).cmp(&rank(k2.get())));
+
+    let mut names: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
+    names.sort_by_key(|k| rank(k));
+
+    for name in names {
+        let mut path = path.to_vec();
+        path.push(&name);
+        // can unwrap because the names are the keys of the table
+        let (mut key, item) = table.get_key_value_mut(&name).unwrap();
+        match item {
+            Item::Value(value) => {
+                let prefix = tidy(
+                    key.leaf_decor()
+                        .prefix()
+                        .and_then(|p| p.as_str())
+                        .unwrap_or(""),
+                );
+                key.fmt();
+                key.leaf_decor_mut().set_prefix(prefix);
+                key.leaf_decor_mut().set_suffix(" ");
+                format_value(value);
+            }
+            Item::Table(t) => format_header(t, &path, order, position),
+            Item::ArrayOfTables(a) => {
+                for t in a.iter_mut() {
+                    format_header(t, &path, order, position);
+                }
+            }
+            Item::None => (),
+        }
+    }
+}
+
+fn format_header(table: &mut Table, path: &[&str], order: Order, position: &mut isize) {
+    if !table.is_implicit() {
+        table.set_position(*position);
+        *position += 1;
+        let prefix = tidy(
+            table
+                .decor()
+                .prefix()
+                .and_then(|p| p.as_str())
+                .unwrap_or(""),
+        );
+        // comments followed by a blank line end the table before, they are separated already
+        if prefix.lines().any(str::is_empty) {
+            table.decor_mut().set_prefix(prefix);
+        } else {
+            table.decor_mut().set_prefix(format!("\n{}", prefix));
+        }
+    }
+    format_table(table, path, order, position);
+}
+
+fn format_value(value: &mut Value) {
+    // a comment after the value is kept
+    let suffix = value
+        .decor()
+        .suffix()
+        .and_then(|s| s.as_str())
+        .unwrap_or("");
+    let suffix = match suffix.trim() {
+        s if s.starts_with('#') => format!(" {}", s),
+        _ => String::new(),
+    };
+
+    format_repr(value);
+    value.decor_mut().set_prefix(" ");
+    value.decor_mut().set_suffix(suffix);
+}
+
+fn format_repr(value: &mut Value) {
+    match value {
+        Value::String(f) => f.fmt(),
+        Value::Integer(f) => f.fmt(),
+        Value::Float(f) => f.fmt(),
+        Value::Boolean(f) => f.fmt(),
+        Value::Datetime(f) => f.fmt(),
+        Value::Array(a) => a.iter_mut().for_each(format_repr),
+        Value::InlineTable(t) => t.iter_mut().for_each(|(_, v)| format_repr(v)),
+    }
+}
+
+fn tidy(prefix: &str) -> String {
+    let mut lines: Vec<&str> = prefix.split('\n').map(str::trim).collect();
+    // the last piece is the indentation of what follows
+    lines.pop();
+    lines.dedup_by(|a, b| a.is_empty() && b.is_empty());
+    lines.iter().map(|l| format!("{}\n", l)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_section() -> anyhow::Result<()> {
+        let source = "
+
+# The section
+[section]
+  desc = 'About things'   # short
+name=\"Things\"
+
+
+
+# The first page
+[[page]]
+path = \"a.md\"
+name = 'A'
+tags = [ 'x', \"y\" ]
+[[page]]
+name = \"B\"
+path = \"b.md\"
+
+# the end
+";
+        let exp = "# The section
+[section]
+name = \"Things\"
+desc = \"About things\" # short
+
+# The first page
+[[page]]
+name = \"A\"
+path = \"a.md\"
+tags = [ \"x\", \"y\" ]
+
+[[page]]
+name = \"B\"
+path = \"b.md\"
+
+# the end
+";
+
+        let formatted = format(source, SectionSpec::keys)?;
+        assert_eq!(formatted, exp);
+        assert_eq!(format(&formatted, SectionSpec::keys)?, exp);
+        Ok(())
+    }
+
+    #[test]
+    fn example_is_formatted() -> anyhow::Result<()> {
+        for spec in [
+            "example/manifest.toml",
+            "example/projects/section.toml",
+            "example/tutorials/section.toml",
+            "example/tutorials/linux/subsection.toml",
+        ] {
+            let source = fs::read_to_string(spec)?;
+            assert_eq!(format(&source, order(Path::new(spec)))?, source, "{}", spec);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::code;
+
+/// Page markdown with its include directives replaced by the included files.
+#[derive(Debug, PartialEq)]
+pub struct Expanded {
+    pub source: String,
+    pub dependencies: Vec<PathBuf>,
+}
+
+/// Read `file` and expand the `{{< include "path" >}}` directives in it, and with `shortcodes`
+/// the `{{ include("path") }}` shortcodes on lines of their own.
+pub fn expand(file: &Path, shortcodes: bool) -> anyhow::Result<Expanded> {
+    let mut expanded = Expanded {
+        source: String::new(),
+        dependencies: Vec::new(),
+    };
+    let mut stack = Vec::new();
+    expand_file(file, 0, shortcodes, &mut stack, &mut expanded)?;
+    Ok(expanded)
+}
+
+fn expand_file(
+    file: &Path,
+    shift: usize,
+    shortcodes: bool,
+    stack: &mut Vec<PathBuf>,
+    expanded: &mut Expanded,
+) -> anyhow::Result<()> {
+    let id = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+    if let Some(i) = stack.iter().position(|f| *f == id) {
+        let cycle: Vec<String> = stack[i..]
+            .iter()
+            .chain(std::iter::once(&id))
+            .map(|f| f.display().to_string())
+            .collect();
+        anyhow::bail!("include cycle: {}", cycle.join(" -> "));
+    }
+
+    let source = match fs::read_to_string(file) {
+        Ok(s) => s,
+        Err(e) => match stack.last() {
+            Some(parent) => {
+                anyhow::bail!(
+                    "cannot include {} in {}: {}",
+                    file.display(),
+                    parent.display(),
+                    e
+                )
+            }
+            None => return Err(e.into()),
+        },
+    };
+    if !expanded.dependencies.contains(&file.to_path_buf()) {
+        expanded.dependencies.push(file.to_path_buf());
+    }
+
+    let source = if stack.is_empty() {
+        source.as_str()
+    } else {
+        strip_front_matter(&source)
+    };
+    let dir = file.parent().unwrap_or(Path::new(""));
+
+    stack.push(id);
+    let code = code::ranges(source, false);
+    let mut offset = 0;
+    let mut level = 0;
+    for line in source.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let start = offset;
+        offset += line.len();
+
+        if code::in_code(&code, start, line) {
+            expanded.source.push_str(line);
+            continue;
+        }
+
+        let path = match directive(trimmed) {
+            None if shortcodes => shortcode(trimmed),
+            path => path,
+        };
+        if let Some(path) = path {
+            expand_file(&dir.join(path), level, shortcodes, stack, expanded)?;
+            if !expanded.source.ends_with('\n') {
+                expanded.source.push('\n');
+            }
+            continue;
+        }
+
+        match heading_level(line) {
+            Some(l) if shift > 0 => {
+                let shifted = (l + shift).min(6);
+                level = shifted;
+                expanded.source.push_str(&"#".repeat(shifted));
+                expanded.source.push_str(&line.trim_start()[l..]);
+            }
+            Some(l) => {
+                level = l;
+                expanded.source.push_str(line);
+            }
+            None => expanded.source.push_str(line),
+        }
+    }
+    stack.pop();
+
+    Ok(())
+}
+
+fn directive(line: &str) -> Option<&str> {
+    let inner = line.strip_prefix("{{<")?.strip_suffix(">}}")?.trim();
+    let path = inner.strip_prefix("include")?.trim();
+    path.strip_prefix('"')?.strip_suffix('"')
+}
+
+fn shortcode(line: &str) -> Option<&str> {
+    let inner = line.strip_prefix("{{")?.strip_suffix("}}")?.trim();
+    let args = inner.strip_prefix("include")?.trim_start();
+    let path = args.strip_prefix('(')?.strip_suffix(')')?.trim();
+    path.strip_prefix('"')
+        .and_then(|p| p.strip_suffix('"'))
+        .or_else(|| path.strip_prefix('\'')?.strip_suffix('\''))
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let level = rest.len() - rest.trim_start_matches('#').len();
+    let after = &rest[level..];
+    let ends = after.is_empty() || after.starts_with([' ', '\t', '\r', '\n']);
+    ((1..=6).contains(&level) && ends).then_some(level)
+}
+
+fn strip_front_matter(source: &str) -> &str {
+    let Some(rest) = source
+        .strip_prefix("+++\n")
+        .or_else(|| source.strip_prefix("+++\r\n"))
+    else {
+        return source;
+    };
+    match rest.find("\n+++") {
+        Some(end) => rest[end + 4..].trim_start_matches(['\r', '\n']),
+        None => source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_includes() -> anyhow::Result<()> {
+        let expanded = expand(Path::new("tests/fixtures/spec4/include/guide.md"), false)?;
+        let exp = "# Guide
+
+Before.
+
+## Setup
+
+Install it:
+
+```
+{{< include \"not/expanded.md\" >}}
+```
+
+### Details
+
+Nested.
+
+After.
+";
+
+        assert_eq!(expanded.source, exp);
+        assert_eq!(
+            expanded.dependencies,
+            vec![
+                PathBuf::from("tests/fixtures/spec4/include/guide.md"),
+                PathBuf::from("tests/fixtures/spec4/include/common/setup.md"),
+                PathBuf::from("tests/fixtures/spec4/include/common/details.md"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn include_cycle() {
+        let err = expand(Path::new("tests/fixtures/spec4/include/cycle_a.md"), false).unwrap_err();
+
+        assert!(err.to_string().starts_with("include cycle: "));
+        assert!(err.to_string().ends_with("cycle_a.md"));
+    }
+
+    #[test]
+    fn include_shortcodes() -> anyhow::Result<()> {
+        let expanded = expand(Path::new("tests/fixtures/spec4/page.md"), true)?;
+
+        assert!(expanded.source.ends_with("}}\n\nShared **setup** steps.\n"));
+        assert_eq!(expanded.dependencies[1], Path::new("tests/fixtures/spec4/snippet.md"));
+        assert_eq!(expand(Path::new("tests/fixtures/spec4/page.md"), false)?.dependencies.len(), 1);
+
+        assert_eq!(shortcode("{{ include('a.md') }}"), Some("a.md"));
+        assert_eq!(shortcode("{{include(\"b.md\")}}"), Some("b.md"));
+        assert_eq!(shortcode("{{ include(\"c.md\") }} and more"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn headings() {
+        assert_eq!(heading_level("# A\n"), Some(1));
+        assert_eq!(heading_level("   ### A"), Some(3));
+        assert_eq!(heading_level("#hashtag"), None);
+        assert_eq!(heading_level("    # code"), None);
+        assert_eq!(heading_level("####### seven"), None);
+    }
+}
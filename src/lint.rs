@@ -0,0 +1,221 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use toml::Spanned;
+use toml::de::{DeTable, DeValue};
+
+use crate::diagnostic::{Diagnostic, Level};
+use crate::discover::normalize;
+
+/// Check the spec files `specs` for likely mistakes, which do not stop the website from being
+/// built. Only errors fail the check, unless `deny_warnings` is set.
+pub fn lint(specs: &[PathBuf], deny_warnings: bool) -> anyhow::Result<()> {
+    let (mut errors, mut warnings) = (0, 0);
+    for spec in specs {
+        let source = fs::read_to_string(spec)?;
+        for issue in issues(spec, &source) {
+            match issue.level() {
+                Level::Error => {
+                    log::error!("{}", issue);
+                    errors += 1;
+                }
+                Level::Warning => {
+                    log::warn!("{}", issue);
+                    warnings += 1;
+                }
+            }
+        }
+    }
+
+    if errors > 0 || (deny_warnings && warnings > 0) {
+        anyhow::bail!(
+            "found {} error(s) and {} warning(s) in the spec files",
+            errors,
+            warnings
+        );
+    }
+    if warnings > 0 {
+        log::info!(
+            "Found {} warning(s) in {} spec file(s)",
+            warnings,
+            specs.len()
+        );
+    } else {
+        log::info!("No issues found in {} spec file(s)", specs.len());
+    }
+    Ok(())
+}
+
+fn issues(path: &Path, source: &str) -> Vec<Diagnostic> {
+    let doc = match DeTable::parse(source) {
+        Ok(doc) => doc,
+        Err(e) => {
+            let diag = Diagnostic::new(Level::Error, e.message().trim_end(), path);
+            return vec![match e.span() {
+                Some(span) => diag.with_span(source, span),
+                None => diag,
+            }];
+        }
+    };
+    let doc = doc.get_ref();
+    let mut lint = Lint {
+        path,
+        source,
+        issues: Vec::new(),
+    };
+
+    let root = !matches!(
+        path.file_name().and_then(|f| f.to_str()),
+        Some("section.toml" | "subsection.toml")
+    );
+    if root {
+        lint.empty(doc, "title", "the website has an empty title");
+        if let Some((_, prepend)) = entry(doc, "href_prepend")
+            && prepend.get_ref().as_str().is_some_and(|p| p.ends_with('/'))
+        {
+            lint.warn(
+                "`href_prepend` ends with a slash",
+                prepend.span(),
+                Some("every href starts with a slash already, remove the trailing one"),
+            );
+        }
+    }
+    for kind in ["section", "subsection"] {
+        if let Some(DeValue::Table(table)) = entry(doc, kind).map(|(_, v)| v.get_ref()) {
+            lint.empty(table, "name", &format!("the {} has an empty name", kind));
+        }
+    }
+
+    let pages = entry(doc, "page").and_then(|(_, v)| v.get_ref().as_array());
+    let mut listed: Vec<PathBuf> = Vec::new();
+    for page in pages.into_iter().flatten() {
+        let DeValue::Table(page) = page.get_ref() else {
+            continue;
+        };
+        lint.empty(page, "name", "a page has an empty name");
+
+        if root
+            && let Some((key, desc)) = entry(page, "desc")
+            && desc.get_ref().as_str().is_some_and(|d| !d.is_empty())
+        {
+            lint.warn(
+                "the description of a page in the root is not displayed anywhere",
+                key,
+                None,
+            );
+        }
+
+        if let Some((_, file)) = entry(page, "path")
+            && let Some(name) = file.get_ref().as_str()
+        {
+            // `./a.md` and `a.md` are the same page
+            let normal = normalize(Path::new(name));
+            if listed.contains(&normal) {
+                lint.warn(
+                    &format!("`{}` is listed more than once", name),
+                    file.span(),
+                    Some("remove all but one of its `[[page]]` entries"),
+                );
+            }
+            listed.push(normal);
+        }
+    }
+
+    lint.issues
+}
+
+struct Lint<'a> {
+    path: &'a Path,
+    source: &'a str,
+    issues: Vec<Diagnostic>,
+}
+
+impl Lint<'_> {
+    fn warn(&mut self, message: &str, span: Range<usize>, help: Option<&str>) {
+        let mut diag =
+            Diagnostic::new(Level::Warning, message, self.path).with_span(self.source, span);
+        if let Some(help) = help {
+            diag = diag.with_help(help);
+        }
+        self.issues.push(diag);
+    }
+
+    fn empty(&mut self, table: &DeTable, key: &str, message: &str) {
+        if let Some((_, value)) = entry(table, key)
+            && value
+                .get_ref()
+                .as_str()
+                .is_some_and(|v| v.trim().is_empty())
+        {
+            self.warn(message, value.span(), None);
+        }
+    }
+}
+
+fn entry<'a, 'i>(
+    table: &'a DeTable<'i>,
+    key: &str,
+) -> Option<(Range<usize>, &'a Spanned<DeValue<'i>>)> {
+    table
+        .iter()
+        .find(|(k, _)| k.get_ref() == key)
+        .map(|(k, v)| (k.span(), v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testing::TempDir;
+
+    #[test]
+    fn lint_manifest() {
+        let source = "title = \"\"
+main_page = \"index.md\"
+href_prepend = \"/~home/\"
+
+[[page]]
+name = \"A\"
+desc = \"Not shown\"
+path = \"a.md\"
+
+[[page]]
+name = \" \"
+path = \"./a.md\"
+";
+        let found: Vec<String> = issues(Path::new("manifest.toml"), source)
+            .iter()
+            .map(|i| i.to_string().lines().next().unwrap_or_default().to_string())
+            .collect();
+
+        assert_eq!(
+            found,
+            [
+                "warning: the website has an empty title",
+                "warning: `href_prepend` ends with a slash",
+                "warning: the description of a page in the root is not displayed anywhere",
+                "warning: a page has an empty name",
+                "warning: `./a.md` is listed more than once",
+            ]
+        );
+        assert!(issues(Path::new("section.toml"), "[section]\nname = \"S\"\n").is_empty());
+    }
+
+    #[test]
+    fn only_errors_fail() -> anyhow::Result<()> {
+        let dir = TempDir::new("lint");
+        let warned = dir.join("manifest.toml");
+        fs::write(&warned, "title = \"\"\nmain_page = \"index.md\"\n")?;
+        let broken = dir.join("section.toml");
+        fs::write(&broken, "[section\n")?;
+
+        assert!(lint(std::slice::from_ref(&warned), false).is_ok());
+        assert!(lint(std::slice::from_ref(&warned), true).is_err());
+        assert!(lint(&[broken], false).is_err());
+        Ok(())
+    }
+}
@@ -3,18 +3,194 @@
 
 #![allow(unused)]
 
-use simplelog::*;
+use std::path::PathBuf;
 
-use minijinja::{AutoEscape, Environment};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use simplelog::*;
 
+mod admonition;
+mod cite;
+mod code;
+mod data;
+mod diagnostic;
+mod diagram;
 mod discover;
+mod extension;
+mod format;
+mod include;
+mod lint;
+mod minify;
+mod nav;
+mod outline;
+mod output;
+mod picture;
 mod render;
+mod scaffold;
 mod spec;
+mod taxonomy;
+#[cfg(test)]
+mod testing;
 mod tree;
+mod validate;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate the website
+    Build(BuildArgs),
+    /// Remove the generated website and the build cache
+    Clean(CleanArgs),
+    /// Print the tree of the website
+    Tree(TreeArgs),
+    /// Create a new site, section or page
+    #[command(subcommand)]
+    New(NewCommand),
+    /// Format the manifest and the section and subsection spec files
+    Fmt(FmtArgs),
+    /// Check the manifest and the section and subsection spec files for likely mistakes
+    Lint(LintArgs),
+}
+
+#[derive(Subcommand)]
+enum NewCommand {
+    /// Create a site with a manifest and a main page
+    Site(NewSiteArgs),
+    /// Create a section, or a subsection inside a section
+    Section(NewSectionArgs),
+    /// Create a page and add it to the spec file next to it
+    Page(NewPageArgs),
+}
+
+#[derive(Args)]
+struct BuildArgs {
+    /// Directory containing the manifest.toml file
+    #[arg(default_value = ".")]
+    root: PathBuf,
+    /// Directory to write the website to
+    #[arg(short, long, default_value = "public")]
+    output: PathBuf,
+    /// Treat unknown keys in spec files as errors
+    #[arg(long)]
+    strict: bool,
+    /// Add markdown files missing from the spec files as pages
+    #[arg(long)]
+    auto_include: bool,
+    /// Include pages and sections marked as drafts
+    #[arg(long)]
+    drafts: bool,
+    /// Minify the generated html, CSS and JavaScript
+    #[arg(long)]
+    minify: bool,
+    /// Print every file the build would write and whether it changes, without writing anything
+    #[arg(long)]
+    dry_run: bool,
+    /// Also remove files in the output directory which the build did not write
+    #[arg(long)]
+    prune: bool,
+}
+
+#[derive(Args)]
+struct CleanArgs {
+    /// Directory containing the manifest.toml file
+    #[arg(default_value = ".")]
+    root: PathBuf,
+    /// Directory the website was written to
+    #[arg(short, long, default_value = "public")]
+    output: PathBuf,
+    /// Also remove files in the output directory which the last build did not write
+    #[arg(long)]
+    prune: bool,
+}
+
+#[derive(Args)]
+struct TreeArgs {
+    /// Directory containing the manifest.toml file
+    #[arg(default_value = ".")]
+    root: PathBuf,
+    /// Format to print the tree in
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Treat unknown keys in spec files as errors
+    #[arg(long)]
+    strict: bool,
+    /// Add markdown files missing from the spec files as pages
+    #[arg(long)]
+    auto_include: bool,
+    /// Include pages and sections marked as drafts
+    #[arg(long)]
+    drafts: bool,
+}
+
+#[derive(Args)]
+struct NewSiteArgs {
+    /// Directory to create the site in
+    dir: PathBuf,
+    /// Title of the website
+    #[arg(long, default_value = "My Website")]
+    title: String,
+}
+
+#[derive(Args)]
+struct NewSectionArgs {
+    /// Directory of the section, in the root of a site or in a section
+    dir: PathBuf,
+    /// Name of the section, the name of the directory by default
+    #[arg(long)]
+    name: Option<String>,
+    /// Description of the section
+    #[arg(long, default_value = "")]
+    desc: String,
+}
+
+#[derive(Args)]
+struct NewPageArgs {
+    /// Markdown file of the page, next to a manifest.toml, section.toml or subsection.toml
+    path: PathBuf,
+    /// Name of the page
+    #[arg(long)]
+    name: String,
+    /// Description of the page
+    #[arg(long, default_value = "")]
+    desc: String,
+}
+
+#[derive(Args)]
+struct FmtArgs {
+    /// Directory containing the manifest.toml file
+    #[arg(default_value = ".")]
+    root: PathBuf,
+    /// Only report the files which are not formatted, failing if there are any
+    #[arg(long)]
+    check: bool,
+}
+
+#[derive(Args)]
+struct LintArgs {
+    /// Directory containing the manifest.toml file
+    #[arg(default_value = ".")]
+    root: PathBuf,
+    /// Fail on warnings as well, not only on errors
+    #[arg(long)]
+    deny_warnings: bool,
+}
 
-use crate::render::{is_empty, is_page, is_section};
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// An indented outline, with the directories which were skipped
+    Text,
+    Json,
+    Toml,
+}
 
 fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
     let logger_cfg = ConfigBuilder::new()
         .set_time_level(LevelFilter::Off)
         .set_location_level(LevelFilter::Off)
@@ -22,26 +198,101 @@ fn main() -> anyhow::Result<()> {
         .set_thread_level(LevelFilter::Off)
         .build();
 
+    // the plan of a dry run and the tree are the output, progress would only clutter it
+    let level = match &cli.command {
+        Command::Build(args) if args.dry_run => LevelFilter::Warn,
+        Command::Tree(_) => LevelFilter::Warn,
+        _ => LevelFilter::Trace,
+    };
     TermLogger::init(
-        LevelFilter::Trace,
+        level,
         logger_cfg,
         TerminalMode::Mixed,
         ColorChoice::Auto,
     );
 
-    let mut env = Environment::new();
-    env.set_auto_escape_callback(|_| AutoEscape::None);
-    env.add_test("page", is_page);
-    env.add_test("section", is_section);
-    env.add_test("empty", is_empty);
-    env.add_template("base.html", include_str!("./templates/base.html"))?;
-    env.add_template("sec_index.html", include_str!("./templates/sec_index.html"))?;
-    env.add_template("sub_index.html", include_str!("./templates/sub_index.html"))?;
-    env.add_template("content.html", include_str!("./templates/content.html"))?;
+    match cli.command {
+        Command::Build(args) => build(args),
+        Command::Clean(args) => clean(args),
+        Command::Tree(args) => tree(args),
+        Command::New(command) => new(command),
+        Command::Fmt(args) => fmt(args),
+        Command::Lint(args) => lint(args),
+    }
+}
 
-    let structure = discover::Structure::collect_from(std::path::Path::new("example/"))?;
-    let tree = structure.into_tree()?;
-    tree.render(std::path::Path::new("/tmp/wgen_out"))?;
+fn build(args: BuildArgs) -> anyhow::Result<()> {
+    let opts = discover::Options {
+        strict: args.strict,
+        auto_include: args.auto_include,
+        drafts: args.drafts,
+    };
+
+    let structure = discover::Structure::collect_from(&args.root)?;
+    let tree = structure.into_tree(&opts)?;
+    tree.validate()?;
+    if !args.dry_run {
+        std::fs::create_dir_all(&args.output)?;
+    }
+    let render_opts = render::RenderOptions {
+        dry_run: args.dry_run,
+        prune: args.prune,
+        minify: args.minify,
+    };
+    tree.render(&args.output, &render_opts)?;
 
     Ok(())
 }
+
+fn clean(args: CleanArgs) -> anyhow::Result<()> {
+    let structure = discover::Structure::collect_from(&args.root)?;
+    let tree = structure.into_tree(&discover::Options::default())?;
+    tree.clean(&args.output, args.prune)
+}
+
+fn tree(args: TreeArgs) -> anyhow::Result<()> {
+    let opts = discover::Options {
+        strict: args.strict,
+        auto_include: args.auto_include,
+        drafts: args.drafts,
+    };
+
+    let structure = discover::Structure::collect_from(&args.root)?;
+    let (tree, skipped) = structure.into_tree_and_skipped(&opts)?;
+    let out = match args.format {
+        Format::Text => tree.outline(&skipped),
+        Format::Json => serde_json::to_string_pretty(&tree)? + "\n",
+        Format::Toml => toml::to_string(&tree)?,
+    };
+    print!("{}", out);
+
+    Ok(())
+}
+
+fn new(command: NewCommand) -> anyhow::Result<()> {
+    match command {
+        NewCommand::Site(args) => scaffold::site(&args.dir, &args.title),
+        NewCommand::Section(args) => {
+            let name = args.name.unwrap_or_else(|| {
+                let name = args.dir.file_name().unwrap_or_default();
+                name.to_string_lossy().to_string()
+            });
+            scaffold::section(&args.dir, &name, &args.desc)
+        }
+        NewCommand::Page(args) => scaffold::page(&args.path, &args.name, &args.desc),
+    }
+}
+
+fn fmt(args: FmtArgs) -> anyhow::Result<()> {
+    let structure = discover::Structure::collect_from(&args.root)?;
+    let changed = format::format_files(&structure.specs(), args.check)?;
+    if args.check && changed > 0 {
+        anyhow::bail!("{} spec file(s) are not formatted", changed);
+    }
+    Ok(())
+}
+
+fn lint(args: LintArgs) -> anyhow::Result<()> {
+    let structure = discover::Structure::collect_from(&args.root)?;
+    lint::lint(&structure.specs(), args.deny_warnings)
+}
@@ -0,0 +1,314 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+const RAW: &[&str] = &["pre", "textarea", "script", "style"];
+
+const BLOCK: &[&str] = &[
+    "!doctype",
+    "html",
+    "head",
+    "body",
+    "meta",
+    "link",
+    "title",
+    "script",
+    "style",
+    "div",
+    "p",
+    "section",
+    "nav",
+    "header",
+    "footer",
+    "main",
+    "article",
+    "aside",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "ul",
+    "ol",
+    "li",
+    "dl",
+    "dt",
+    "dd",
+    "table",
+    "thead",
+    "tbody",
+    "tr",
+    "th",
+    "td",
+    "blockquote",
+    "pre",
+    "hr",
+    "br",
+    "figure",
+    "figcaption",
+    "picture",
+    "source",
+    "details",
+    "summary",
+    "svg",
+];
+
+/// Drop comments and the whitespace between block elements, and collapse the rest.
+pub fn html(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut rest = source;
+    // whether nothing but a block element tag was written since the last text
+    let mut after_block = true;
+
+    while !rest.is_empty() {
+        let next = rest.find('<').unwrap_or(rest.len());
+        let len = out.len();
+        push_text(
+            &mut out,
+            &rest[..next],
+            after_block,
+            rest[next..].starts_with('<').then(|| &rest[next..]),
+        );
+        after_block &= out.len() == len;
+        rest = &rest[next..];
+        if rest.is_empty() {
+            break;
+        }
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = match comment.find("-->") {
+                Some(end) => &comment[end + 3..],
+                None => "",
+            };
+            continue;
+        }
+
+        let end = rest.find('>').map(|i| i + 1).unwrap_or(rest.len());
+        let tag = &rest[..end];
+        out.push_str(tag);
+        rest = &rest[end..];
+        after_block = is_block(tag);
+
+        let name = tag_name(tag);
+        if tag.starts_with("</") || tag.ends_with("/>") || !RAW.contains(&name.as_str()) {
+            continue;
+        }
+        let close = format!("</{}", name);
+        let len = find_ignore_case(rest, &close).unwrap_or(rest.len());
+        after_block &= len == 0;
+        match name.as_str() {
+            "script" => out.push_str(js(&rest[..len]).trim_end()),
+            "style" => out.push_str(&css(&rest[..len])),
+            _ => out.push_str(&rest[..len]),
+        }
+        rest = &rest[len..];
+    }
+
+    out
+}
+
+fn push_text(out: &mut String, text: &str, after_block: bool, next: Option<&str>) {
+    let mut words = text.split_ascii_whitespace().peekable();
+    let leading = text.starts_with(|c: char| c.is_ascii_whitespace());
+    let trailing = text.ends_with(|c: char| c.is_ascii_whitespace());
+
+    let before_block = next.is_none_or(is_block);
+    if words.peek().is_none() {
+        if !text.is_empty() && !after_block && !before_block {
+            out.push(' ');
+        }
+        return;
+    }
+
+    if leading && !after_block {
+        out.push(' ');
+    }
+    out.push_str(&words.collect::<Vec<_>>().join(" "));
+    if trailing && !before_block {
+        out.push(' ');
+    }
+}
+
+/// The offset of ASCII `needle` in `haystack`, ignoring case.
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|w| w.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+fn is_block(tag: &str) -> bool {
+    BLOCK.contains(&tag_name(tag).as_str())
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|c| !c.is_ascii_whitespace() && *c != '>' && *c != '/')
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Drop comments and collapse whitespace, removing it around punctuation.
+pub fn css(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut space = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            '"' | '\'' => {
+                push_space(&mut out, &mut space);
+                out.push(c);
+                string(&mut out, &mut chars, c);
+            }
+            c if c.is_whitespace() => space = true,
+            '{' | '}' | ';' | ',' | '>' | ':' => {
+                // `a :hover` differs from `a:hover`
+                if c == ':' && space && !out.ends_with(['{', ';']) {
+                    out.push(' ');
+                }
+                if c == '}' && out.ends_with(';') {
+                    out.pop();
+                }
+                out.push(c);
+                space = false;
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+            }
+            c => {
+                push_space(&mut out, &mut space);
+                out.push(c);
+            }
+        }
+    }
+
+    out
+}
+
+/// Drop comments, indentation and blank lines.
+pub fn js(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|c| *c != '\n') {
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            '"' | '\'' | '`' => {
+                out.push(c);
+                string(&mut out, &mut chars, c);
+            }
+            c => out.push(c),
+        }
+    }
+
+    out.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| l.to_string() + "\n")
+        .collect()
+}
+
+fn string(out: &mut String, chars: &mut impl Iterator<Item = char>, quote: char) {
+    let mut escaped = false;
+    for c in chars.by_ref() {
+        out.push(c);
+        if c == quote && !escaped {
+            break;
+        }
+        escaped = c == '\\' && !escaped;
+    }
+}
+
+fn push_space(out: &mut String, space: &mut bool) {
+    if *space && !out.is_empty() && !out.ends_with(['{', '}', ';', ',', '>', ':']) {
+        out.push(' ');
+    }
+    *space = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minify_html() {
+        let source = "<!DOCTYPE html>
+<html>
+  <head>
+    <!-- a comment -->
+    <title>Page</title>
+    <style>
+      a { color: red; }
+    </style>
+  </head>
+  <body>
+    <div id=\"content\">
+      <p>Some   <em>text</em> <a href=\"#\">here</a>
+      </p>
+      <pre><code>  keep
+    this</code></pre>
+    </div>
+    <script>
+      // greet
+      console.log(\"a  // b\");
+    </script>
+  </body>
+</html>
+";
+        let exp = "<!DOCTYPE html><html><head><title>Page</title>\
+                   <style>a{color:red}</style></head><body><div id=\"content\">\
+                   <p>Some <em>text</em> <a href=\"#\">here</a></p>\
+                   <pre><code>  keep\n    this</code></pre></div>\
+                   <script>console.log(\"a  // b\");</script></body></html>";
+
+        assert_eq!(html(source), exp);
+    }
+
+    #[test]
+    fn minify_html_tags() {
+        let source = "<div title=\"a<b\">\n  <p>x</p>\n</div>\n<PRE> a </Pre>\n<p>y</p>";
+
+        assert_eq!(
+            html(source),
+            "<div title=\"a<b\"><p>x</p></div><PRE> a </Pre><p>y</p>"
+        );
+    }
+
+    #[test]
+    fn minify_css() {
+        let source = "/* theme */\n#content a :hover,\nli > p {\n  content: \"a ; b\";\n  \
+                      margin: 0 auto;\n}\n";
+
+        assert_eq!(
+            css(source),
+            "#content a :hover,li>p{content:\"a ; b\";margin:0 auto}"
+        );
+    }
+}
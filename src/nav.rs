@@ -0,0 +1,161 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::collections::HashMap;
+
+use crate::tree::*;
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Link<'a> {
+    name: &'a str,
+    href: &'a str,
+}
+
+/// Links shown around the content of a page.
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+pub struct Nav<'a> {
+    prev: Option<Link<'a>>,
+    next: Option<Link<'a>>,
+    breadcrumbs: Vec<Link<'a>>,
+}
+
+impl Tree {
+    /// Compute the navigation links of every page, keyed by the page href.
+    pub fn navigation(&self) -> HashMap<&str, Nav<'_>> {
+        let mut nav = HashMap::new();
+        let main = Link {
+            name: self.main_page().name(),
+            href: self.main_page().href(),
+        };
+
+        let root_pages = self.iter().filter_map(|e| match e {
+            TreeEntry::Page(page) => Some(page),
+            _ => None,
+        });
+        link_siblings(root_pages, std::slice::from_ref(&main), &mut nav);
+
+        for section in self.sections() {
+            let crumbs = [main.clone(), Link::to_section(section)];
+            let pages = section.iter().filter_map(|e| match e {
+                SectionEntry::Page(page) => Some(page),
+                _ => None,
+            });
+            link_siblings(pages, &crumbs, &mut nav);
+
+            for entry in section.iter() {
+                if let SectionEntry::Subsection(subsection) = entry {
+                    let crumbs = [
+                        crumbs[0].clone(),
+                        crumbs[1].clone(),
+                        Link::to_subsection(subsection),
+                    ];
+                    link_siblings(subsection.iter(), &crumbs, &mut nav);
+                }
+            }
+        }
+
+        nav
+    }
+}
+
+impl<'a> Link<'a> {
+    fn to_page(page: &'a Page) -> Self {
+        Self {
+            name: page.name(),
+            href: page.href(),
+        }
+    }
+
+    fn to_section(section: &'a Section) -> Self {
+        Self {
+            name: section.name(),
+            href: section.href(),
+        }
+    }
+
+    fn to_subsection(subsection: &'a Subsection) -> Self {
+        Self {
+            name: subsection.name(),
+            href: subsection.href(),
+        }
+    }
+}
+
+fn link_siblings<'a>(
+    pages: impl Iterator<Item = &'a Page>,
+    crumbs: &[Link<'a>],
+    nav: &mut HashMap<&'a str, Nav<'a>>,
+) {
+    let pages: Vec<&Page> = pages.collect();
+    let listed: Vec<&Page> = pages.iter().copied().filter(|p| !p.is_unlisted()).collect();
+
+    for page in pages {
+        let mut page_nav = Nav {
+            breadcrumbs: crumbs.to_vec(),
+            ..Default::default()
+        };
+
+        if let Some(i) = listed.iter().position(|p| std::ptr::eq(*p, page)) {
+            page_nav.prev = i.checked_sub(1).map(|i| Link::to_page(listed[i]));
+            page_nav.next = listed.get(i + 1).map(|p| Link::to_page(p));
+        }
+
+        nav.insert(page.href(), page_nav);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subsection_navigation() {
+        let tree = Tree::structure_into_tree_expect();
+        let nav = tree.navigation();
+
+        let exp = Nav {
+            prev: Some(Link {
+                name: "1 MD",
+                href: "/d1/s1/1.html",
+            }),
+            next: None,
+            breadcrumbs: vec![
+                Link {
+                    name: "WGEN Webpage",
+                    href: "/",
+                },
+                Link {
+                    name: "D1 section",
+                    href: "/d1/",
+                },
+                Link {
+                    name: "S1 subsection",
+                    href: "/d1/s1/",
+                },
+            ],
+        };
+
+        assert_eq!(nav["/d1/s1/2.html"], exp);
+    }
+
+    #[test]
+    fn root_navigation() {
+        let tree = Tree::structure_into_tree_expect();
+        let nav = tree.navigation();
+
+        let exp = Nav {
+            prev: None,
+            next: Some(Link {
+                name: "Second page in the root",
+                href: "/2.html",
+            }),
+            breadcrumbs: vec![Link {
+                name: "WGEN Webpage",
+                href: "/",
+            }],
+        };
+
+        assert_eq!(nav["/1.html"], exp);
+        assert_eq!(nav.len(), tree.pages().count());
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::discover::Skipped;
+use crate::tree::*;
+
+impl Tree {
+    /// An indented outline of the website, one line per section, subsection and page with its
+    /// href and source, and the `skipped` directories in the place they were found.
+    pub fn outline(&self, skipped: &[Skipped]) -> String {
+        let mut out = String::new();
+        let main = self.main_page();
+        self.line(&mut out, 0, main.name(), main.href(), main.file(), "");
+
+        for entry in self.iter() {
+            match entry {
+                TreeEntry::Page(p) => self.page(&mut out, 1, p),
+                TreeEntry::Section(s) => {
+                    let flags = marks(s.is_draft(), s.is_unlisted());
+                    self.line(&mut out, 1, s.name(), s.href(), s.path(), &flags);
+                    for entry in s.iter() {
+                        match entry {
+                            SectionEntry::Page(p) => self.page(&mut out, 2, p),
+                            SectionEntry::Subsection(sub) => {
+                                let flags = marks(sub.is_draft(), sub.is_unlisted());
+                                self.line(&mut out, 2, sub.name(), sub.href(), sub.path(), &flags);
+                                sub.iter().for_each(|p| self.page(&mut out, 3, p));
+                                self.skipped(&mut out, 3, sub.path(), skipped);
+                            }
+                        }
+                    }
+                    self.skipped(&mut out, 2, s.path(), skipped);
+                }
+            }
+        }
+        self.skipped(&mut out, 1, self.root(), skipped);
+
+        out
+    }
+
+    fn page(&self, out: &mut String, depth: usize, page: &Page) {
+        let flags = marks(page.is_draft(), page.is_unlisted());
+        self.line(out, depth, page.name(), page.href(), page.file(), &flags);
+    }
+
+    fn line(
+        &self,
+        out: &mut String,
+        depth: usize,
+        name: &str,
+        href: &str,
+        source: &Path,
+        flags: &str,
+    ) {
+        let source = source.strip_prefix(self.root()).unwrap_or(source);
+        // can unwrap because writing to a string does not fail
+        writeln!(
+            out,
+            "{}{}  {} <- {}{}",
+            indent(depth),
+            name,
+            href,
+            source.display(),
+            flags
+        )
+        .unwrap();
+    }
+
+    fn skipped(&self, out: &mut String, depth: usize, dir: &Path, skipped: &[Skipped]) {
+        for s in skipped.iter().filter(|s| s.path.parent() == Some(dir)) {
+            let path = s.path.strip_prefix(self.root()).unwrap_or(&s.path);
+            // can unwrap because writing to a string does not fail
+            writeln!(
+                out,
+                "{}{}/  skipped: {}",
+                indent(depth),
+                path.display(),
+                s.reason
+            )
+            .unwrap();
+        }
+    }
+}
+
+fn indent(depth: usize) -> String {
+    "  ".repeat(depth)
+}
+
+fn marks(draft: bool, unlisted: bool) -> String {
+    let mut flags = String::new();
+    if draft {
+        flags.push_str(" [draft]");
+    }
+    if unlisted {
+        flags.push_str(" [unlisted]");
+    }
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    #[test]
+    fn outline_example() {
+        let skipped = [Skipped {
+            path: PathBuf::from("example/tutorials/windows"),
+            reason: "subsection.toml not found or is not a file".to_string(),
+        }];
+        let outline = Tree::example_tree().outline(&skipped);
+        let lines: Vec<&str> = outline.lines().collect();
+
+        assert_eq!(lines[0], "Example Webpage  / <- landing.md");
+        assert!(lines.contains(&"  Tutorials  /tutorials/ <- tutorials"));
+        assert!(lines.contains(
+            &"      Why Gentoo is the best?  /tutorials/linux/gentoo.html <- tutorials/linux/gentoo.md"
+        ));
+        assert_eq!(
+            lines[lines.len() - 3],
+            "    tutorials/windows/  skipped: subsection.toml not found or is not a file"
+        );
+    }
+}
@@ -0,0 +1,555 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::cell::{Cell, RefCell, RefMut};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::minify;
+
+/// Paths in the output directory which are never removed, like the repository of a website
+/// deployed with git, or the custom domain of GitHub Pages.
+pub const PROTECTED: &[&str] = &[".git", "CNAME"];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BuildCache {
+    version: String,
+    outdir: PathBuf,
+    outputs: BTreeMap<PathBuf, Entry>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Entry {
+    key: String,
+    inputs: BTreeMap<PathBuf, String>,
+    files: BTreeMap<PathBuf, PathBuf>,
+}
+
+/// How a file of the build compares to the one in the output directory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    New,
+    Changed,
+    Unchanged,
+    Removed,
+}
+
+/// Writes the generated files of the website, minifying html, CSS and JavaScript when asked to.
+#[derive(Debug, Default)]
+pub struct Output {
+    outdir: PathBuf,
+    minify: bool,
+    cache: Option<PathBuf>,
+    dry_run: bool,
+    last: BuildCache,
+    next: RefCell<BTreeMap<PathBuf, Entry>>,
+    current: RefCell<Option<(PathBuf, PathBuf)>>,
+    plan: RefCell<BTreeMap<PathBuf, (PathBuf, Change)>>,
+    skipped: Cell<usize>,
+    before: Cell<usize>,
+    after: Cell<usize>,
+}
+
+impl Output {
+    /// Write to `outdir`, using and updating the build cache in `cache` if given.
+    pub fn new(outdir: &Path, minify: bool, cache: Option<PathBuf>, dry_run: bool) -> Self {
+        let last = cache
+            .as_ref()
+            .and_then(|c| fs::read_to_string(c).ok())
+            .and_then(|s| match serde_json::from_str::<BuildCache>(&s) {
+                Ok(last) => Some(last),
+                Err(e) => {
+                    log::warn!("Ignoring the build cache: {}", e);
+                    None
+                }
+            })
+            .filter(|last| last.version == env!("CARGO_PKG_VERSION") && last.outdir == outdir)
+            .unwrap_or_default();
+        log::trace!("{} outputs in the build cache", last.outputs.len());
+
+        Self {
+            outdir: outdir.to_path_buf(),
+            minify,
+            cache,
+            dry_run,
+            last,
+            ..Default::default()
+        }
+    }
+
+    /// Start making the output at `path` from `source`, unless it is up to date.
+    pub fn up_to_date(&self, path: &Path, key: &str, source: &Path) -> bool {
+        let path = self.relative(path);
+        let last = self.last.outputs.get(&path).filter(|entry| {
+            entry.key == key
+                && entry
+                    .inputs
+                    .iter()
+                    .all(|(file, h)| fs::read(file).is_ok_and(|c| hash(&c) == *h))
+                && entry.files.keys().all(|f| self.outdir.join(f).is_file())
+        });
+
+        match last {
+            Some(entry) => {
+                if self.dry_run {
+                    let mut plan = self.plan.borrow_mut();
+                    for (file, source) in &entry.files {
+                        plan.insert(file.clone(), (source.clone(), Change::Unchanged));
+                    }
+                }
+                self.next.borrow_mut().insert(path, entry.clone());
+                self.current.replace(None);
+                self.skipped.set(self.skipped.get() + 1);
+                true
+            }
+            None => {
+                let entry = Entry {
+                    key: key.to_string(),
+                    ..Default::default()
+                };
+                self.next.borrow_mut().insert(path.clone(), entry);
+                self.current.replace(Some((path, source.to_path_buf())));
+                false
+            }
+        }
+    }
+
+    /// Record that the current output is made from `file`.
+    pub fn depend(&self, file: &Path) -> anyhow::Result<()> {
+        let hash = hash(&fs::read(file)?);
+        if let Some(entry) = self.current_entry().as_mut() {
+            entry.inputs.insert(file.to_path_buf(), hash);
+        }
+        Ok(())
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// The files recorded as the inputs of the outputs so far, canonicalized.
+    pub fn inputs(&self) -> BTreeSet<PathBuf> {
+        let next = self.next.borrow();
+        let inputs = next.values().flat_map(|entry| entry.inputs.keys());
+        inputs.filter_map(|f| f.canonicalize().ok()).collect()
+    }
+
+    /// Write `contents` of the current output to `path`, minified according to the file
+    /// extension.
+    pub fn write(&self, path: &Path, contents: &str) -> anyhow::Result<()> {
+        let source = self.current.borrow().as_ref().map(|(_, s)| s.clone());
+        let source = source.unwrap_or_default();
+        let minified = self.minify.then(|| minified(path, contents)).flatten();
+        match minified {
+            Some(m) => {
+                self.before.set(self.before.get() + contents.len());
+                self.after.set(self.after.get() + m.len());
+                self.write_bytes(path, m.as_bytes(), &source)
+            }
+            None => self.write_bytes(path, contents.as_bytes(), &source),
+        }
+    }
+
+    /// Write `contents`, made from `source`, to `path` as they are.
+    pub fn write_bytes(&self, path: &Path, contents: &[u8], source: &Path) -> anyhow::Result<()> {
+        if self.dry_run {
+            let change = match fs::read(path) {
+                Ok(old) if old == contents => Change::Unchanged,
+                Ok(_) => Change::Changed,
+                Err(_) => Change::New,
+            };
+            self.planned(path, source, change);
+        } else {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(path, contents)?;
+        }
+        self.produced(path, source);
+        Ok(())
+    }
+
+    /// Copy the file `from`, made from `source`, to `path`.
+    pub fn copy(&self, from: &Path, path: &Path, source: &Path) -> anyhow::Result<()> {
+        if self.dry_run {
+            let change = match (fs::read(from), fs::read(path)) {
+                (_, Err(_)) => Change::New,
+                (Ok(new), Ok(old)) if new != old => Change::Changed,
+                _ => Change::Unchanged,
+            };
+            self.planned(path, source, change);
+        } else {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::copy(from, path)?;
+        }
+        self.produced(path, source);
+        Ok(())
+    }
+
+    /// Remove the files the last build wrote which this one did not, save the build cache and
+    /// log a summary of the build, or print the plan of a dry run. With `prune`, every other
+    /// file in the output directory is removed as well, apart from the [`PROTECTED`] paths and
+    /// the ones in `prune`.
+    pub fn finish(self, prune: Option<&[String]>) -> anyhow::Result<()> {
+        let next = self.next.into_inner();
+        let kept: BTreeSet<PathBuf> = next
+            .values()
+            .flat_map(|e| e.files.keys().cloned())
+            .collect();
+        let mut removed = BTreeSet::new();
+        let built = self.last.outputs.values().flat_map(|e| e.files.keys());
+        for file in built.filter(|f| !kept.contains(*f)) {
+            let full = self.outdir.join(file);
+            if !full.is_file() {
+                continue;
+            }
+            if !self.dry_run {
+                log::info!("Removing {}, which is no longer built", full.display());
+                fs::remove_file(&full)?;
+                remove_empty_dirs(&self.outdir, file)?;
+            }
+            removed.insert(file.clone());
+        }
+        if let Some(keep) = prune {
+            removed.extend(remove_stale(&self.outdir, keep, &kept, self.dry_run)?);
+        }
+
+        if self.dry_run {
+            let mut plan = self.plan.into_inner();
+            for file in removed {
+                plan.insert(file, (PathBuf::new(), Change::Removed));
+            }
+            print_plan(&plan);
+            return Ok(());
+        }
+
+        if let Some(cache) = &self.cache {
+            let build = BuildCache {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                outdir: self.outdir.clone(),
+                outputs: next,
+            };
+            if let Some(dir) = cache.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(cache, serde_json::to_string_pretty(&build)?)?;
+        }
+        log::trace!("The build has {} file(s)", kept.len());
+
+        if self.skipped.get() > 0 {
+            log::info!("Kept {} unchanged output(s)", self.skipped.get());
+        }
+        let (before, after) = (self.before.get(), self.after.get());
+        if self.minify && before > 0 {
+            let saved = before.saturating_sub(after);
+            log::info!(
+                "Minifying saved {} of {} bytes ({:.1}%)",
+                saved,
+                before,
+                saved as f64 * 100.0 / before as f64
+            );
+        }
+        Ok(())
+    }
+
+    fn produced(&self, path: &Path, source: &Path) {
+        let path = self.relative(path);
+        if let Some(entry) = self.current_entry().as_mut() {
+            entry.files.insert(path, source.to_path_buf());
+        }
+    }
+
+    fn planned(&self, path: &Path, source: &Path, change: Change) {
+        let path = self.relative(path);
+        self.plan
+            .borrow_mut()
+            .insert(path, (source.to_path_buf(), change));
+    }
+
+    fn current_entry(&self) -> Option<RefMut<'_, Entry>> {
+        let (current, _) = self.current.borrow().clone()?;
+        RefMut::filter_map(self.next.borrow_mut(), |next| next.get_mut(&current)).ok()
+    }
+
+    fn relative(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.outdir)
+            .unwrap_or(path)
+            .to_path_buf()
+    }
+}
+
+fn print_plan(plan: &BTreeMap<PathBuf, (PathBuf, Change)>) {
+    let mut counts = BTreeMap::new();
+    for (file, (source, change)) in plan {
+        let label = match change {
+            Change::New => "new",
+            Change::Changed => "changed",
+            Change::Unchanged => "unchanged",
+            Change::Removed => "removed",
+        };
+        *counts.entry(label).or_insert(0) += 1;
+        if *change == Change::Removed {
+            println!("{:<10} {}", label, file.display());
+        } else {
+            println!("{:<10} {} <- {}", label, file.display(), source.display());
+        }
+    }
+
+    let count = |label| counts.get(label).copied().unwrap_or(0);
+    println!(
+        "{} new, {} changed, {} unchanged, {} removed",
+        count("new"),
+        count("changed"),
+        count("unchanged"),
+        count("removed")
+    );
+}
+
+/// Remove the directories of `file` in `outdir` which are left empty.
+fn remove_empty_dirs(outdir: &Path, file: &Path) -> anyhow::Result<()> {
+    for dir in file.ancestors().skip(1) {
+        let full = outdir.join(dir);
+        if dir.as_os_str().is_empty() || full.read_dir()?.next().is_some() {
+            break;
+        }
+        fs::remove_dir(&full)?;
+    }
+    Ok(())
+}
+
+/// Remove the files in `outdir` which are not `produced`, and the directories left empty,
+/// or only list them in a dry run.
+pub fn remove_stale(
+    outdir: &Path,
+    keep: &[String],
+    produced: &BTreeSet<PathBuf>,
+    dry_run: bool,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let keep: Vec<&Path> = PROTECTED
+        .iter()
+        .copied()
+        .chain(keep.iter().map(String::as_str))
+        .map(Path::new)
+        .collect();
+    let mut removed = Vec::new();
+    remove_stale_in(
+        outdir,
+        Path::new(""),
+        &keep,
+        produced,
+        dry_run,
+        &mut removed,
+    )?;
+    Ok(removed)
+}
+
+fn remove_stale_in(
+    outdir: &Path,
+    dir: &Path,
+    keep: &[&Path],
+    produced: &BTreeSet<PathBuf>,
+    dry_run: bool,
+    removed: &mut Vec<PathBuf>,
+) -> anyhow::Result<()> {
+    let Ok(read_dir) = outdir.join(dir).read_dir() else {
+        return Ok(());
+    };
+
+    for entry in read_dir.flatten() {
+        let path = dir.join(entry.file_name());
+        if keep.iter().any(|k| path.starts_with(k)) {
+            continue;
+        }
+        let full = outdir.join(&path);
+        // a link to a directory is removed like a file, what it links to is left alone
+        if entry.file_type()?.is_dir() {
+            remove_stale_in(outdir, &path, keep, produced, dry_run, removed)?;
+            if !dry_run && full.read_dir()?.next().is_none() {
+                fs::remove_dir(&full)?;
+            }
+        } else if !produced.contains(&path) {
+            if !dry_run {
+                log::info!("Removing {}", full.display());
+                fs::remove_file(&full)?;
+            }
+            removed.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// `contents` minified according to the extension of `path`, if it is html, CSS or JavaScript.
+pub fn minified(path: &Path, contents: &str) -> Option<String> {
+    match path.extension().and_then(|e| e.to_str()).unwrap_or("") {
+        "html" => Some(minify::html(contents)),
+        "css" => Some(minify::css(contents)),
+        "js" => Some(minify::js(contents)),
+        _ => None,
+    }
+}
+
+/// `name` with the start of `hash`, a hex digest of the contents, put before its extension,
+/// like `style.0123abcd.css`, so browsers fetch the file again whenever it changes.
+pub fn fingerprint(name: &str, hash: &str) -> String {
+    let hash = &hash[..hash.len().min(8)];
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !ext.contains('/') => format!("{}.{}.{}", stem, hash, ext),
+        _ => format!("{}.{}", name, hash),
+    }
+}
+
+/// The hex sha256 digest of `contents`.
+pub fn hash(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testing::TempDir;
+
+    #[test]
+    fn incremental() -> anyhow::Result<()> {
+        let tmp = TempDir::new("output");
+        let outdir = tmp.join("public");
+        let cache = Some(tmp.join("build.json"));
+        let input = tmp.join("page.md");
+        fs::create_dir_all(&outdir)?;
+        fs::write(&input, "# Page")?;
+
+        let build = |pages: &[&str]| -> anyhow::Result<Vec<bool>> {
+            let out = Output::new(&outdir, false, cache.clone(), false);
+            let mut made = Vec::new();
+            for page in pages {
+                let path = outdir.join(page);
+                let up_to_date = out.up_to_date(&path, "key", &input);
+                if !up_to_date {
+                    out.depend(&input)?;
+                    out.write(&path, "<p>Page</p>")?;
+                }
+                made.push(!up_to_date);
+            }
+            out.finish(None)?;
+            Ok(made)
+        };
+
+        assert_eq!(build(&["a.html", "b.html"])?, [true, true]);
+        assert_eq!(build(&["a.html", "b.html"])?, [false, false]);
+        fs::write(&input, "# Changed")?;
+        assert_eq!(build(&["a.html"])?, [true]);
+        assert!(!outdir.join("b.html").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn built_files() -> anyhow::Result<()> {
+        let tmp = TempDir::new("built");
+        let outdir = tmp.join("public");
+        let cache = Some(tmp.join("build.json"));
+        fs::create_dir_all(&outdir)?;
+        fs::write(outdir.join("notes.txt"), "")?;
+
+        let build = |pages: &[&str]| -> anyhow::Result<()> {
+            let out = Output::new(&outdir, false, cache.clone(), false);
+            for page in pages {
+                let path = outdir.join(page);
+                if !out.up_to_date(&path, "key", Path::new("page.md")) {
+                    out.write(&path, "<p>Page</p>")?;
+                }
+            }
+            out.finish(None)
+        };
+
+        build(&["index.html", "old/page.html"])?;
+        build(&["index.html"])?;
+        assert!(outdir.join("index.html").is_file());
+        assert!(!outdir.join("old").exists());
+        build(&[])?;
+        assert!(!outdir.join("index.html").exists());
+        assert!(outdir.join("notes.txt").is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn dry_run() -> anyhow::Result<()> {
+        let outdir = TempDir::new("dry-run");
+        fs::write(outdir.join("a.html"), "<p>Old</p>")?;
+        fs::write(outdir.join("b.html"), "<p>Page</p>")?;
+
+        let out = Output::new(&outdir, false, None, true);
+        for page in ["a.html", "b.html", "c.html"] {
+            out.up_to_date(&outdir.join(page), "key", Path::new("page.md"));
+            out.write(&outdir.join(page), "<p>Page</p>")?;
+        }
+        let plan: Vec<_> = out.plan.take().into_iter().collect();
+        let change = |file: &str, change| (PathBuf::from(file), (PathBuf::from("page.md"), change));
+
+        assert_eq!(
+            plan,
+            [
+                change("a.html", Change::Changed),
+                change("b.html", Change::Unchanged),
+                change("c.html", Change::New),
+            ]
+        );
+        assert_eq!(fs::read_to_string(outdir.join("a.html"))?, "<p>Old</p>");
+        assert!(!outdir.join("c.html").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn stale_files() -> anyhow::Result<()> {
+        let outdir = TempDir::new("stale");
+        for file in [
+            "index.html",
+            "old/page.html",
+            ".git/HEAD",
+            "CNAME",
+            "keep/me.txt",
+        ] {
+            let file = outdir.join(file);
+            fs::create_dir_all(file.parent().unwrap())?;
+            fs::write(file, "")?;
+        }
+        let produced = BTreeSet::from([PathBuf::from("index.html")]);
+
+        let keep = ["keep".to_string()];
+        assert_eq!(remove_stale(&outdir, &keep, &produced, true)?.len(), 1);
+        assert!(outdir.join("old/page.html").is_file());
+        assert_eq!(
+            remove_stale(&outdir, &keep, &produced, false)?,
+            [PathBuf::from("old/page.html")]
+        );
+        assert!(!outdir.join("old").exists());
+        for file in ["index.html", ".git/HEAD", "CNAME", "keep/me.txt"] {
+            assert!(outdir.join(file).is_file());
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stale_links() -> anyhow::Result<()> {
+        let tmp = TempDir::new("links");
+        let outdir = tmp.join("public");
+        fs::create_dir_all(tmp.join("elsewhere"))?;
+        fs::create_dir_all(&outdir)?;
+        fs::write(tmp.join("elsewhere/file.txt"), "")?;
+        std::os::unix::fs::symlink(tmp.join("elsewhere"), outdir.join("link"))?;
+
+        assert_eq!(
+            remove_stale(&outdir, &[], &BTreeSet::new(), false)?,
+            [PathBuf::from("link")]
+        );
+        assert!(!outdir.join("link").exists());
+        assert!(tmp.join("elsewhere/file.txt").is_file());
+        Ok(())
+    }
+}
@@ -0,0 +1,375 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+
+use crate::discover::normalize;
+use crate::output::{Output, fingerprint, hash};
+use crate::render::CACHE_DIR;
+use crate::spec::ImagesSpec;
+use crate::tree::*;
+
+/// How images are resized, and where from.
+pub struct Pipeline<'a> {
+    pub spec: &'a ImagesSpec,
+    pub root: &'a Path,
+    pub cache: PathBuf,
+    pub output: &'a Output,
+}
+
+struct Variants {
+    src: String,
+    width: u32,
+    height: u32,
+    original: Vec<(String, u32)>,
+    webp: Vec<(String, u32)>,
+}
+
+impl Tree {
+    /// Copy the images referenced in the html of `page` to the website in `outdir`, together
+    /// with resized and WebP variants, all fingerprinted, and turn their `<img>` tags into
+    /// `<picture>` elements choosing between them.
+    pub fn pictures(
+        &self,
+        out: &Output,
+        html: &str,
+        page: &Page,
+        outdir: &Path,
+    ) -> anyhow::Result<String> {
+        if !self.images().enabled {
+            return Ok(html.to_string());
+        }
+        let pipeline = Pipeline {
+            spec: self.images(),
+            root: self.root(),
+            cache: self.root().join(CACHE_DIR).join("images"),
+            output: out,
+        };
+        let dir = page.file().parent().unwrap_or(Path::new(""));
+        pipeline.rewrite(html, dir, outdir)
+    }
+}
+
+impl Pipeline<'_> {
+    /// Rewrite the `<img>` tags of `html`, with sources relative to `dir` in the site, which is
+    /// written to `outdir`.
+    pub fn rewrite(&self, html: &str, dir: &Path, outdir: &Path) -> anyhow::Result<String> {
+        let mut out = String::new();
+        let mut rest = html;
+
+        while let Some(start) = rest.find("<img ") {
+            out.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find('>') else {
+                break;
+            };
+            let tag = &rest[start..start + end + 1];
+            rest = &rest[start + end + 1..];
+
+            let attributes = attributes(tag);
+            let src = attributes
+                .iter()
+                .find(|(k, _)| *k == "src")
+                .map(|(_, v)| *v);
+            let file = src.and_then(|s| local_image(s, dir));
+            let (Some(src), Some(file)) = (src, file) else {
+                out.push_str(tag);
+                continue;
+            };
+            if !file.canonicalize()?.starts_with(self.root.canonicalize()?) {
+                log::warn!(
+                    "Image {} is outside of the site, not resizing it",
+                    file.display()
+                );
+                out.push_str(tag);
+                continue;
+            }
+            // where the page links to, which has to be inside the website
+            let dest = dir.strip_prefix(self.root).unwrap_or(dir).join(decode(src));
+            let dest = normalize(&dest);
+            if dest.components().any(|c| c == Component::ParentDir) {
+                anyhow::bail!(
+                    "image `{}` in {} leads out of the website",
+                    src,
+                    dir.display()
+                );
+            }
+            let file = normalize(&file);
+            let variants = self.variants(&file, src, &outdir.join(dest))?;
+            out.push_str(&picture(&attributes, &variants));
+        }
+        out.push_str(rest);
+
+        Ok(out)
+    }
+
+    fn variants(&self, file: &Path, src: &str, dest: &Path) -> anyhow::Result<Variants> {
+        self.output.depend(file)?;
+        let bytes = fs::read(file)?;
+        let hash = hash(&bytes);
+        let (width, height) = image::image_dimensions(file)?;
+
+        // can unwrap because only files with an extension are local images
+        let ext = file.extension().unwrap().to_string_lossy().to_lowercase();
+        let source = file.strip_prefix(self.root).unwrap_or(file);
+        let dest_stem = dest.file_stem().unwrap_or_default().to_string_lossy();
+
+        let mut widths: Vec<u32> = self
+            .spec
+            .widths
+            .iter()
+            .copied()
+            .filter(|w| *w < width)
+            .collect();
+        widths.sort();
+        widths.dedup();
+
+        let mut variants = Variants {
+            src: fingerprint(src, &hash),
+            width,
+            height,
+            original: Vec::new(),
+            webp: Vec::new(),
+        };
+        let mut decoded: Option<DynamicImage> = None;
+        let webp = self.spec.webp && ext != "webp";
+        let formats = [Some(ext.as_str()), webp.then_some("webp")];
+
+        for format in formats.into_iter().flatten() {
+            let list = if format != ext {
+                &mut variants.webp
+            } else {
+                &mut variants.original
+            };
+            for w in widths.iter().copied().chain(std::iter::once(width)) {
+                let suffix = if w == width {
+                    format!(".{}", format)
+                } else {
+                    format!("-{}w.{}", w, format)
+                };
+                let target =
+                    dest.with_file_name(fingerprint(&(dest_stem.to_string() + &suffix), &hash));
+                let name = fingerprint(&format!("{}{}", stem(src), suffix), &hash);
+                if w == width && format == ext {
+                    self.output.write_bytes(&target, &bytes, source)?;
+                    list.push((name, w));
+                    continue;
+                }
+
+                let cached = self.cache.join(format!("{}-{}.{}", &hash[..16], w, format));
+                // a dry run only tells whether the variant would change
+                if !cached.is_file() && !self.output.dry_run() {
+                    let image = match &decoded {
+                        Some(image) => image,
+                        None => decoded.insert(image::load_from_memory(&bytes)?),
+                    };
+                    let h = (u64::from(height) * u64::from(w) / u64::from(width)).max(1) as u32;
+                    log::trace!("Making a {}x{} {} of {}", w, h, format, file.display());
+                    save(&image.resize_exact(w, h, FilterType::Lanczos3), &cached)?;
+                }
+
+                self.output.copy(&cached, &target, source)?;
+                list.push((name, w));
+            }
+        }
+
+        Ok(variants)
+    }
+}
+
+fn save(image: &DynamicImage, path: &Path) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let format = ImageFormat::from_path(path)?;
+    match format {
+        // jpeg has no alpha channel
+        ImageFormat::Jpeg => {
+            DynamicImage::ImageRgb8(image.to_rgb8()).save_with_format(path, format)?
+        }
+        _ => image.save_with_format(path, format)?,
+    }
+    Ok(())
+}
+
+fn local_image(src: &str, dir: &Path) -> Option<PathBuf> {
+    if src.contains("://") || src.starts_with('/') || src.starts_with("data:") {
+        return None;
+    }
+    let file = dir.join(decode(src));
+    let ext = file.extension()?.to_str()?.to_lowercase();
+    let known = ["png", "jpg", "jpeg", "webp"].contains(&ext.as_str());
+    (known && file.is_file()).then_some(file)
+}
+
+fn stem(src: &str) -> &str {
+    match src.rfind('.') {
+        Some(i) if !src[i..].contains('/') => &src[..i],
+        _ => src,
+    }
+}
+
+fn picture(attributes: &[(&str, &str)], variants: &Variants) -> String {
+    let srcset = |list: &[(String, u32)]| -> String {
+        list.iter()
+            .map(|(src, w)| format!("{} {}w", src, w))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let sizes = format!(
+        "(max-width: {}px) 100vw, {}px",
+        variants.width, variants.width
+    );
+
+    let mut html = String::from("<picture>");
+    if !variants.webp.is_empty() {
+        html.push_str(&format!(
+            "<source type=\"image/webp\" srcset=\"{}\" sizes=\"{}\" />",
+            srcset(&variants.webp),
+            sizes
+        ));
+    }
+
+    html.push_str("<img");
+    for (key, value) in attributes {
+        if *key == "src" {
+            html.push_str(&format!(" src=\"{}\"", variants.src));
+        } else if !["width", "height", "loading", "srcset", "sizes"].contains(key) {
+            html.push_str(&format!(" {}=\"{}\"", key, value));
+        }
+    }
+    if variants.original.len() > 1 {
+        html.push_str(&format!(
+            " srcset=\"{}\" sizes=\"{}\"",
+            srcset(&variants.original),
+            sizes
+        ));
+    }
+    html.push_str(&format!(
+        " width=\"{}\" height=\"{}\" loading=\"lazy\" /></picture>",
+        variants.width, variants.height
+    ));
+    html
+}
+
+fn attributes(tag: &str) -> Vec<(&str, &str)> {
+    let mut attributes = Vec::new();
+    let mut rest = tag.trim_start_matches("<img").trim_end_matches('>');
+    while let Some(eq) = rest.find("=\"") {
+        let key = rest[..eq].trim();
+        let value_start = eq + 2;
+        let Some(len) = rest[value_start..].find('"') else {
+            break;
+        };
+        attributes.push((key, &rest[value_start..value_start + len]));
+        rest = &rest[value_start + len + 1..];
+    }
+    attributes
+}
+
+fn decode(src: &str) -> String {
+    let bytes = src.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testing::TempDir;
+
+    #[test]
+    fn rewrite_images() -> anyhow::Result<()> {
+        let tmp = TempDir::new("pictures");
+        let root = tmp.join("site");
+        let outdir = tmp.join("public");
+        fs::create_dir_all(root.join("img"))?;
+        DynamicImage::new_rgb8(1000, 500).save(root.join("img/big dot.png"))?;
+
+        let spec = ImagesSpec {
+            enabled: true,
+            widths: vec![200, 2000],
+            webp: true,
+        };
+        let pipeline = Pipeline {
+            spec: &spec,
+            root: &root,
+            cache: tmp.join("cache"),
+            output: &Output::default(),
+        };
+        let html = "<p><img src=\"img/big%20dot.png\" alt=\"Big\" /> \
+                    <img src=\"https://example.com/a.png\" alt=\"\" /></p>";
+        let res = pipeline.rewrite(html, &root, &outdir)?;
+        let h = &hash(&fs::read(root.join("img/big dot.png"))?)[..8];
+        let exp = format!(
+            "<p><picture><source type=\"image/webp\" \
+             srcset=\"img/big%20dot-200w.{h}.webp 200w, img/big%20dot.{h}.webp 1000w\" \
+             sizes=\"(max-width: 1000px) 100vw, 1000px\" />\
+             <img src=\"img/big%20dot.{h}.png\" alt=\"Big\" \
+             srcset=\"img/big%20dot-200w.{h}.png 200w, img/big%20dot.{h}.png 1000w\" \
+             sizes=\"(max-width: 1000px) 100vw, 1000px\" \
+             width=\"1000\" height=\"500\" loading=\"lazy\" /></picture> \
+             <img src=\"https://example.com/a.png\" alt=\"\" /></p>"
+        );
+
+        assert_eq!(res, exp);
+        let webp = outdir.join(format!("img/big dot-200w.{}.webp", h));
+        assert_eq!(image::image_dimensions(webp)?, (200, 100));
+        assert!(outdir.join(format!("img/big dot.{}.png", h)).is_file());
+        assert_eq!(tmp.join("cache").read_dir()?.count(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn image_destinations() -> anyhow::Result<()> {
+        let tmp = TempDir::new("destinations");
+        let root = tmp.join("site");
+        let outdir = tmp.join("public");
+        fs::create_dir_all(root.join("img"))?;
+        fs::create_dir_all(root.join("notes"))?;
+        DynamicImage::new_rgb8(10, 10).save(root.join("img/dot.png"))?;
+
+        let spec = ImagesSpec {
+            enabled: true,
+            widths: vec![],
+            webp: false,
+        };
+        let pipeline = Pipeline {
+            spec: &spec,
+            root: &root,
+            cache: tmp.join("cache"),
+            output: &Output::default(),
+        };
+        let h = &hash(&fs::read(root.join("img/dot.png"))?)[..8];
+        let html = "<img src=\"../img/dot.png\" />";
+        pipeline.rewrite(html, &root.join("notes"), &outdir)?;
+        assert!(outdir.join(format!("img/dot.{}.png", h)).is_file());
+
+        // inside the site, but the page would link out of the website
+        let html = "<img src=\"../site/img/dot.png\" />";
+        assert!(pipeline.rewrite(html, &root, &outdir).is_err());
+        assert!(!tmp.join(format!("site/img/dot.{}.png", h)).exists());
+        Ok(())
+    }
+}
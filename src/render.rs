@@ -1,16 +1,44 @@
 // Copyright 2025 Szymon Sztuka (lysolaka)
 // Distributed under the terms of the GNU General Public License v3 or later
 
-use markdown::{Constructs, Options, ParseOptions};
-use minijinja::{AutoEscape, Environment, context};
+use markdown::{CompileOptions, Constructs, Options, ParseOptions};
+use minijinja::{AutoEscape, Environment, Error, ErrorKind, State, Value, context};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
-use std::io::prelude::*;
 
+use crate::admonition;
+use crate::cite;
+use crate::data::{self, DATA_DIR};
+use crate::diagram;
+use crate::extension;
+use crate::include;
+use crate::nav::Nav;
+use crate::output::{self, Output, fingerprint, hash};
+use crate::spec::MarkdownSpec;
+use crate::taxonomy::{Taxonomy, slugify};
 use crate::tree::*;
 
-const SCRIPT_JS: &'static str = include_str!("./templates/script.js");
-const STYLE_CSS: &'static str = include_str!("./templates/style.css");
+/// Directory next to the manifest with templates overriding the built-in ones.
+pub const TEMPLATE_DIR: &str = "templates";
+/// Directory next to the manifest keeping results of earlier builds.
+pub const CACHE_DIR: &str = ".wgen-cache";
+
+const SCRIPT_JS: &str = include_str!("./templates/script.js");
+const STYLE_CSS: &str = include_str!("./templates/style.css");
+const ASSETS: &[(&str, &str)] = &[("style.css", STYLE_CSS), ("script.js", SCRIPT_JS)];
+// stays on the first line of the page, so line numbers in template errors match the file
+const SHORTCODES_IMPORT: &str = r#"{% from "shortcodes.html" import figure, callout, embed %}"#;
+/// Template temp holding the files read by the `include` function, which the page depends on.
+const INCLUDED: &str = "included";
+
+/// Settings of a single build, on top of the ones in the manifest.
+#[derive(Debug, Default)]
+pub struct RenderOptions {
+    pub dry_run: bool,
+    pub prune: bool,
+    pub minify: bool,
+}
 
 impl Tree {
     pub fn sections(&self) -> impl Iterator<Item = &Section> {
@@ -48,83 +76,302 @@ impl Tree {
         root_pages.chain(sec_pages).chain(sub_pages)
     }
 
-    pub fn render(&self, outdir: &Path) -> anyhow::Result<()> {
-        let md_opts = Options {
-            parse: ParseOptions {
-                constructs: Constructs {
-                    math_flow: true,
-                    math_text: true,
-                    ..Constructs::gfm()
-                },
-                ..ParseOptions::gfm()
-            },
-            ..Options::gfm()
+    /// Render the website to `outdir`, or only print what would be written in a dry run. With
+    /// `prune`, files in `outdir` the build did not write are removed, apart from the kept ones.
+    pub fn render(&self, outdir: &Path, opts: &RenderOptions) -> anyhow::Result<()> {
+        let minify = self.minify() || opts.minify;
+        // read once, so that the hash and the templates see the same data
+        let data = data::load(&self.root().join(DATA_DIR))?;
+        let env = self.environment(&data, minify)?;
+        self.check_templates(&env)?;
+        let taxonomies = self.taxonomies();
+        let site = self.site_hash(&data, minify)?;
+        let cache = self.root().join(CACHE_DIR).join("build.json");
+        let out = Output::new(outdir, minify, Some(cache), opts.dry_run);
+
+        self.render_sections(outdir, &env, &out, &site)?;
+        self.render_subsections(outdir, &env, &out, &site)?;
+        self.render_pages(outdir, &env, &out, &site, &taxonomies)?;
+        self.render_taxonomies(outdir, &env, &out, &site, &taxonomies)?;
+
+        // render sidebar
+        let outpath = outdir.join("sidebar.html");
+        if !out.up_to_date(&outpath, &site, Path::new("manifest.toml")) {
+            log::info!("Rendering sidebar to {}", outpath.display());
+            let tmpl = env.get_template("sidebar.html")?;
+            let html = tmpl.render(context! { tree => self, ctx => self.context(), taxonomies })?;
+            out.write(&outpath, &html)?;
+        }
+
+        // render the main page
+        let outpath = outdir.join("index.html");
+        let main_key = key(&site, self.main_page())?;
+        if !out.up_to_date(&outpath, &main_key, self.source(self.main_page().file())) {
+            log::trace!(
+                "Reading main page contents from {}",
+                self.main_page().file().display()
+            );
+            let page = context! {
+                ..Value::from_serialize(self.main_page()),
+                ..Value::from_serialize(Nav::default()),
+                ..context! { terms => page_terms(self.main_page(), &taxonomies) }
+            };
+            let page_content = self.page_markdown(&env, &out, self.main_page().file(), &page)?;
+            let page_content = self.page_to_html(
+                &out,
+                self.main_page().file(),
+                self.main_page().bibliography(),
+                &page_content,
+            )?;
+            let page_content = self.pictures(&out, &page_content, self.main_page(), outdir)?;
+
+            log::info!("Rendering main page to {}", outpath.display());
+            let tmpl = env.get_template("content.html")?;
+            let html = tmpl.render(
+                context! { ctx => self.context(), page, script => SCRIPT_JS, page_content },
+            )?;
+            out.write(&outpath, &html)?;
+        }
+
+        for file in self.orphans_left(&out) {
+            log::warn!("{} is not listed in any spec file", file.display());
+        }
+
+        // todo make style.css a template as well (allow for themes)
+        for (name, file) in self.assets(minify) {
+            let outpath = outdir.join(&file);
+            if out.up_to_date(&outpath, &file, Path::new(name)) {
+                continue;
+            }
+            log::info!("Writing {} to {}", name, outpath.display());
+            // can unwrap because every asset is in the list
+            let contents = ASSETS.iter().find(|(n, _)| *n == name).unwrap().1;
+            out.write(&outpath, contents)?;
+        }
+
+        // the site hash leaves out the dates of pages, which site.json lists
+        let outpath = outdir.join("site.json");
+        if self.site_json()
+            && !out.up_to_date(&outpath, &key(&site, self)?, Path::new("manifest.toml"))
+        {
+            log::info!("Writing the site tree to {}", outpath.display());
+            out.write(&outpath, &serde_json::to_string_pretty(self)?)?;
+        }
+
+        let keep = (opts.prune && self.owns(outdir)?).then(|| self.keep());
+        out.finish(keep)
+    }
+
+    /// Remove the files the last build recorded writing to `outdir`, and the build cache. With
+    /// `prune`, every other file in `outdir` is removed too, apart from the kept ones.
+    pub fn clean(&self, outdir: &Path, prune: bool) -> anyhow::Result<()> {
+        if prune && outdir.is_dir() && !self.owns(outdir)? {
+            anyhow::bail!(
+                "{} contains the site itself, not removing anything",
+                outdir.display()
+            );
+        }
+        // nothing is built, so finishing removes everything the last build wrote
+        let cache = self.root().join(CACHE_DIR).join("build.json");
+        Output::new(outdir, false, Some(cache), false).finish(prune.then(|| self.keep()))?;
+
+        let cache = self.root().join(CACHE_DIR);
+        if cache.is_dir() {
+            log::info!("Removing {}", cache.display());
+            fs::remove_dir_all(cache)?;
+        }
+        Ok(())
+    }
+
+    fn source<'a>(&self, file: &'a Path) -> &'a Path {
+        file.strip_prefix(self.root()).unwrap_or(file)
+    }
+
+    fn owns(&self, outdir: &Path) -> anyhow::Result<bool> {
+        // nothing to remove from an output directory which does not exist yet
+        let Ok(outdir) = outdir.canonicalize() else {
+            return Ok(true);
         };
+        let owns = !self.root().canonicalize()?.starts_with(&outdir);
+        if !owns {
+            log::warn!(
+                "{} contains the site, stale files in it are not removed",
+                outdir.display()
+            );
+        }
+        Ok(owns)
+    }
+
+    fn site_hash(&self, data: &Value, minify: bool) -> anyhow::Result<String> {
+        let mut tree = serde_json::to_value(self)?;
+        drop_dates(&mut tree);
+        let mut source = format!(
+            "{}\n{}\n{:?}\n{:?}\n{}\n",
+            env!("CARGO_PKG_VERSION"),
+            tree,
+            self.markdown(),
+            self.images(),
+            minify
+        );
+
+        let dir = self.root().join(TEMPLATE_DIR);
+        if let Ok(read_dir) = dir.read_dir() {
+            let mut files: Vec<_> = read_dir.flatten().map(|e| e.path()).collect();
+            files.sort();
+            for path in files.into_iter().filter(|p| p.is_file()) {
+                let contents = hash(&fs::read(&path)?);
+                source.push_str(&format!("{} {}\n", path.display(), contents));
+            }
+        }
+        source.push_str(&serde_json::to_string(data)?);
+
+        Ok(hash(source.as_bytes()))
+    }
 
+    fn environment(&self, data: &Value, minify: bool) -> anyhow::Result<Environment<'static>> {
         let mut env = Environment::new();
         env.set_auto_escape_callback(|_| AutoEscape::None);
         env.add_test("page", is_page);
         env.add_test("section", is_section);
         env.add_test("empty", is_empty);
+        env.add_filter("slug", slugify);
+        env.add_function("include", include);
+        let assets = self.assets(minify);
+        let href_prepend = self.href_prepend().to_string();
+        env.add_function("asset", move |name: &str| match assets.get(name) {
+            Some(file) => Ok(format!("{}/{}", href_prepend, file)),
+            None => Err(Error::new(
+                ErrorKind::InvalidOperation,
+                format!("unknown asset `{}`", name),
+            )),
+        });
+        env.add_global("data", data.clone());
         env.add_template("base.html", include_str!("./templates/base.html"))?;
         env.add_template("sec_index.html", include_str!("./templates/sec_index.html"))?;
         env.add_template("sub_index.html", include_str!("./templates/sub_index.html"))?;
         env.add_template("sidebar.html", include_str!("./templates/sidebar.html"))?;
         env.add_template("content.html", include_str!("./templates/content.html"))?;
+        env.add_template("taxonomy.html", include_str!("./templates/taxonomy.html"))?;
+        env.add_template("term.html", include_str!("./templates/term.html"))?;
+        env.add_template("shortcodes.html", include_str!("./templates/shortcodes.html"))?;
+
+        let dir = self.root().join(TEMPLATE_DIR);
+        if let Ok(read_dir) = dir.read_dir() {
+            let mut files: Vec<_> = read_dir.flatten().map(|e| e.path()).collect();
+            files.sort();
+            for path in files.into_iter().filter(|p| p.is_file()) {
+                let Some(name) = path.file_name() else {
+                    continue;
+                };
+                let name = name.to_string_lossy().to_string();
+                log::info!("Using template {}", path.display());
+                let source = fs::read_to_string(&path)?;
+                env.add_template_owned(name, source)?;
+            }
+        }
 
-        self.render_sections(outdir, &env)?;
-        self.render_subsections(outdir, &env)?;
-        self.render_pages(outdir, &env)?;
-
-        // render sidebar
-        let outpath = outdir.join("sidebar.html");
-        log::info!("Rendering sidebar to {}", outpath.display());
-        let outfile = fs::File::create(outpath)?;
-        let tmpl = env.get_template("sidebar.html")?;
-        tmpl.render_to_write(context! { tree => self, ctx => self.context() }, outfile)?;
-
-        // render the main page
-        let outpath = outdir.join("index.html");
-        log::trace!(
-            "Reading main page contents from {}",
-            self.main_page().file().display()
-        );
-        let page_content = fs::read_to_string(self.main_page().file())?;
+        Ok(env)
+    }
 
-        let page_content = match markdown::to_html_with_options(&page_content, &md_opts) {
-            Ok(s) => s,
-            Err(e) => anyhow::bail!("{}", e),
-        };
+    fn assets(&self, minify: bool) -> BTreeMap<&'static str, String> {
+        ASSETS
+            .iter()
+            .map(|(name, contents)| {
+                let minified = minify.then(|| output::minified(Path::new(name), contents));
+                let contents = minified.flatten().unwrap_or(contents.to_string());
+                (*name, fingerprint(name, &hash(contents.as_bytes())))
+            })
+            .collect()
+    }
 
-        log::info!("Rendering main page to {}", outpath.display());
-        let outfile = fs::File::create(outpath)?;
+    fn check_templates(&self, env: &Environment) -> anyhow::Result<()> {
+        let named = self
+            .pages()
+            .filter_map(|p| Some((p.template()?, p.file())))
+            .chain(self.sections().filter_map(|s| Some((s.template()?, s.path()))))
+            .chain(self.subsections().filter_map(|s| Some((s.template()?, s.path()))));
+
+        let mut missing = 0;
+        for (template, user) in named {
+            if env.get_template(template).is_err() {
+                log::error!(
+                    "Template `{}` used by {} does not exist",
+                    template,
+                    user.display()
+                );
+                missing += 1;
+            }
+        }
 
-        let tmpl = env.get_template("content.html")?;
-        tmpl.render_to_write(
-                context! { ctx => self.context(), page => self.main_page(), script => SCRIPT_JS, page_content },
-                outfile,
-            )?;
+        if missing > 0 {
+            anyhow::bail!(
+                "{} missing template(s), add them to the `{}` directory",
+                missing,
+                TEMPLATE_DIR
+            );
+        }
+        Ok(())
+    }
 
-        // todo make style.css a template as well (allow for themes)
-        let outpath = outdir.join("style.css");
-        log::info!("Writing style.css to {}", outpath.display());
-        let mut outfile = fs::File::create(outpath)?;
-        outfile.write_all(STYLE_CSS.as_bytes())?;
+    fn render_taxonomies(
+        &self,
+        outdir: &Path,
+        env: &Environment,
+        out: &Output,
+        site: &str,
+        taxonomies: &[Taxonomy],
+    ) -> anyhow::Result<()> {
+        let index_tmpl = env.get_template("taxonomy.html")?;
+        let term_tmpl = env.get_template("term.html")?;
+
+        for taxonomy in taxonomies.iter().filter(|t| !t.is_empty()) {
+            // can unwrap because all hrefs start with a slash
+            let dir = outdir.join(Path::new(taxonomy.href()).strip_prefix("/").unwrap());
+            // taxonomies are made from the tree, so the site hash covers them
+            let outpath = dir.join("index.html");
+            let manifest = Path::new("manifest.toml");
+            if !out.up_to_date(&outpath, site, manifest) {
+                log::info!("Rendering {} index to {}", taxonomy.name(), outpath.display());
+                let html = index_tmpl.render(
+                    context! { ctx => self.context(), taxonomy, script => SCRIPT_JS },
+                )?;
+                out.write(&outpath, &html)?;
+            }
+
+            for term in taxonomy.iter() {
+                // can unwrap because all hrefs start with a slash
+                let outpath = outdir.join(Path::new(term.href()).strip_prefix("/").unwrap());
+                if out.up_to_date(&outpath, site, manifest) {
+                    continue;
+                }
+                log::trace!("Rendering {} to {}", taxonomy.name(), outpath.display());
+                let html = term_tmpl.render(
+                    context! { ctx => self.context(), taxonomy, term, script => SCRIPT_JS },
+                )?;
+                out.write(&outpath, &html)?;
+            }
+        }
 
         Ok(())
     }
 
-    fn render_sections(&self, outdir: &Path, env: &Environment) -> anyhow::Result<()> {
-        let tmpl = env.get_template("sec_index.html")?;
-
+    fn render_sections(
+        &self,
+        outdir: &Path,
+        env: &Environment,
+        out: &Output,
+        site: &str,
+    ) -> anyhow::Result<()> {
         let sec_count = self.sections().count();
         for (i, s) in self.sections().enumerate() {
             let outpath = {
                 let p = Path::new(s.href()).strip_prefix("/").unwrap();
                 outdir.join(p).join("index.html")
             };
-            let outdir = outpath.parent().unwrap_or_else(|| Path::new(""));
-            fs::create_dir_all(outdir)?;
+            let source = self.source(s.index().unwrap_or(s.path()));
+            if out.up_to_date(&outpath, &key(site, s)?, source) {
+                continue;
+            }
 
             log::info!(
                 "[{}/{}] Rendering section index to {}",
@@ -133,28 +380,43 @@ impl Tree {
                 outpath.display()
             );
 
-            let outfile = fs::File::create(outpath)?;
+            let sec_content = match s.index() {
+                Some(index) => {
+                    log::trace!("Reading section introduction from {}", index.display());
+                    let sec = Value::from_serialize(s);
+                    let source = self.page_markdown(env, out, index, &sec)?;
+                    Some(self.page_to_html(out, index, None, &source)?)
+                }
+                None => None,
+            };
 
-            tmpl.render_to_write(
-                context! { ctx => self.context(), sec => s, script => SCRIPT_JS },
-                outfile,
+            let tmpl = env.get_template(s.template().unwrap_or("sec_index.html"))?;
+            let html = tmpl.render(
+                context! { ctx => self.context(), sec => s, script => SCRIPT_JS, sec_content },
             )?;
+            out.write(&outpath, &html)?;
         }
 
         Ok(())
     }
 
-    fn render_subsections(&self, outdir: &Path, env: &Environment) -> anyhow::Result<()> {
-        let tmpl = env.get_template("sub_index.html")?;
-
+    fn render_subsections(
+        &self,
+        outdir: &Path,
+        env: &Environment,
+        out: &Output,
+        site: &str,
+    ) -> anyhow::Result<()> {
         let sub_count = self.subsections().count();
         for (i, s) in self.subsections().enumerate() {
             let outpath = {
                 let p = Path::new(s.href()).strip_prefix("/").unwrap();
                 outdir.join(p).join("index.html")
             };
-            let outdir = outpath.parent().unwrap_or_else(|| Path::new(""));
-            fs::create_dir_all(outdir)?;
+            let source = self.source(s.index().unwrap_or(s.path()));
+            if out.up_to_date(&outpath, &key(site, s)?, source) {
+                continue;
+            }
 
             log::info!(
                 "[{}/{}] Rendering subsection index to {}",
@@ -163,43 +425,54 @@ impl Tree {
                 outpath.display()
             );
 
-            let outfile = fs::File::create(outpath)?;
+            let sec_content = match s.index() {
+                Some(index) => {
+                    log::trace!("Reading subsection introduction from {}", index.display());
+                    let sec = Value::from_serialize(s);
+                    let source = self.page_markdown(env, out, index, &sec)?;
+                    Some(self.page_to_html(out, index, None, &source)?)
+                }
+                None => None,
+            };
 
-            tmpl.render_to_write(
-                context! { ctx => self.context(), sec => s, script => SCRIPT_JS },
-                outfile,
+            let tmpl = env.get_template(s.template().unwrap_or("sub_index.html"))?;
+            let html = tmpl.render(
+                context! { ctx => self.context(), sec => s, script => SCRIPT_JS, sec_content },
             )?;
+            out.write(&outpath, &html)?;
         }
 
         Ok(())
     }
 
-    fn render_pages(&self, outdir: &Path, env: &Environment) -> anyhow::Result<()> {
-        let md_opts = Options {
-            parse: ParseOptions {
-                constructs: Constructs {
-                    math_flow: true,
-                    math_text: true,
-                    ..Constructs::gfm()
-                },
-                ..ParseOptions::gfm()
-            },
-            ..Options::gfm()
-        };
-
-        let tmpl = env.get_template("content.html")?;
+    fn render_pages(
+        &self,
+        outdir: &Path,
+        env: &Environment,
+        out: &Output,
+        site: &str,
+        taxonomies: &[Taxonomy],
+    ) -> anyhow::Result<()> {
+        let nav = self.navigation();
 
         let page_count = self.pages().count();
         for (i, p) in self.pages().enumerate() {
             // can unwrap because all hrefs start with a slash
             let outpath = Path::new(p.href()).strip_prefix("/").unwrap();
             let outpath = outdir.join(outpath);
-            let outdir = outpath.parent().unwrap_or_else(|| Path::new(""));
-            fs::create_dir_all(outdir)?;
+            if out.up_to_date(&outpath, &key(site, p)?, self.source(p.file())) {
+                log::trace!("[{}/{}] {} is unchanged", i + 1, page_count, p.file().display());
+                continue;
+            }
 
             log::trace!("[{}/{}] Reading {}", i + 1, page_count, p.file().display());
 
-            let page_content = fs::read_to_string(p.file())?;
+            let page = context! {
+                ..Value::from_serialize(p),
+                ..Value::from_serialize(&nav[p.href()]),
+                ..context! { terms => page_terms(p, taxonomies) }
+            };
+            let page_content = self.page_markdown(env, out, p.file(), &page)?;
 
             log::info!(
                 "[{}/{}] Rendering {} to {}",
@@ -209,20 +482,200 @@ impl Tree {
                 outpath.display()
             );
 
-            let page_content = match markdown::to_html_with_options(&page_content, &md_opts) {
-                Ok(s) => s,
-                Err(e) => anyhow::bail!("{}", e),
-            };
-
-            let outfile = fs::File::create(outpath)?;
+            let page_content = self.page_to_html(out, p.file(), p.bibliography(), &page_content)?;
+            let page_content = self.pictures(out, &page_content, p, outdir)?;
 
-            tmpl.render_to_write(
-                context! { ctx => self.context(), page => p, script => SCRIPT_JS, page_content},
-                outfile,
+            let tmpl = env.get_template(p.template().unwrap_or("content.html"))?;
+            let html = tmpl.render(
+                context! { ctx => self.context(), page, script => SCRIPT_JS, page_content },
             )?;
+            out.write(&outpath, &html)?;
         }
         Ok(())
     }
+
+    /// The orphans of the tree which none of the pages built into `out` included.
+    fn orphans_left(&self, out: &Output) -> Vec<&Path> {
+        let inputs = out.inputs();
+        self.orphans()
+            .iter()
+            .filter(|f| !f.canonicalize().is_ok_and(|f| inputs.contains(&f)))
+            .map(|f| f.as_path())
+            .collect()
+    }
+
+    fn page_markdown(
+        &self,
+        env: &Environment,
+        out: &Output,
+        file: &Path,
+        page: &Value,
+    ) -> anyhow::Result<String> {
+        let expanded = include::expand(file, self.markdown().templates)?;
+        log::trace!(
+            "{} is built from {} file(s)",
+            file.display(),
+            expanded.dependencies.len()
+        );
+        for dependency in &expanded.dependencies {
+            out.depend(dependency)?;
+        }
+        let source = expanded.source;
+        if !self.markdown().templates {
+            return Ok(source);
+        }
+
+        log::trace!("Rendering {} as a template", file.display());
+        let source = format!("{}{}", SHORTCODES_IMPORT, source);
+        let ctx = context! { ctx => self.context(), page, tree => self };
+        let name = file.display().to_string();
+        let tmpl = env.template_from_named_str(&name, &source)?;
+        let (html, state) = tmpl.render_and_return_state(ctx)?;
+        let included = state.get_temp(INCLUDED).and_then(|v| v.try_iter().ok());
+        for dependency in included.into_iter().flatten() {
+            out.depend(Path::new(&dependency.to_string()))?;
+        }
+        Ok(html)
+    }
+
+    fn page_to_html(
+        &self,
+        out: &Output,
+        file: &Path,
+        bibliography: Option<&Path>,
+        source: &str,
+    ) -> anyhow::Result<String> {
+        let spec = self.markdown();
+        let bibliography = match bibliography {
+            Some(b) => Some(b.to_path_buf()),
+            None => spec.bibliography.as_ref().map(|b| self.root().join(b)),
+        };
+        let Some(bibliography) = bibliography else {
+            return self.markdown_to_html(source, out.dry_run());
+        };
+
+        out.depend(&bibliography)?;
+        let bib = cite::load(&bibliography)?;
+        let (source, cited) = cite::cite(source, &bib, spec.citations);
+        log::trace!(
+            "{} cites {} of {} entries",
+            file.display(),
+            cited.len(),
+            bib.len()
+        );
+        let html = self.markdown_to_html(&source, out.dry_run())?;
+        Ok(html + &cite::references(&cited, spec.citations))
+    }
+
+    /// The html of markdown `source`. A dry run does not run the diagram commands, leaving
+    /// diagrams which are not cached as code.
+    fn markdown_to_html(&self, source: &str, dry_run: bool) -> anyhow::Result<String> {
+        let spec = self.markdown();
+        let options = markdown_options(spec);
+        let (mut source, admonitions) = admonition::expand(source, &options.parse)?;
+        // after the admonitions, so it differs from their placeholders
+        let Some(mark) = extension::unused_chars(&source).next() else {
+            anyhow::bail!("no character left to use as a placeholder");
+        };
+        if spec.definition_lists {
+            source = extension::definition_lists(&source, &options.parse, mark)?;
+        }
+        let mut headings = Vec::new();
+        if spec.heading_attributes {
+            (source, headings) = extension::heading_attributes(&source, &options.parse, mark)?;
+        }
+
+        let mut html = match markdown::to_html_with_options(&source, &options) {
+            Ok(s) => admonition::finish(&s, &admonitions),
+            Err(e) => anyhow::bail!("{}", e),
+        };
+        if spec.definition_lists {
+            html = extension::finish_definition_lists(&html, mark);
+        }
+        if spec.heading_attributes {
+            html = extension::finish_heading_attributes(&html, &headings, mark);
+        }
+        if !spec.diagrams.is_empty() {
+            let cache = self.root().join(CACHE_DIR).join("diagrams");
+            html = diagram::render(&html, &spec.diagrams, &cache, dry_run)?;
+        }
+        Ok(html)
+    }
+}
+
+fn key(site: &str, item: &impl serde::Serialize) -> anyhow::Result<String> {
+    let item = serde_json::to_string(item)?;
+    Ok(hash(format!("{}\n{}", site, item).as_bytes()))
+}
+
+/// The tags and categories of `page`, linked where their taxonomy has a page for them.
+fn page_terms(page: &Page, taxonomies: &[Taxonomy]) -> Value {
+    let links = |name: &str, terms| match taxonomies.iter().find(|t| t.name() == name) {
+        Some(taxonomy) => Value::from_serialize(taxonomy.links(terms)),
+        None => Value::from(Vec::<Value>::new()),
+    };
+    context! {
+        tags => links("tags", page.tags()),
+        categories => links("categories", page.categories()),
+    }
+}
+
+fn drop_dates(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.remove("date");
+            map.values_mut().for_each(drop_dates);
+        }
+        serde_json::Value::Array(list) => list.iter_mut().for_each(drop_dates),
+        _ => (),
+    }
+}
+
+fn include(state: &State, path: &str) -> Result<String, Error> {
+    if state.lookup("page").is_none() {
+        return Err(Error::new(
+            ErrorKind::InvalidOperation,
+            "include can only be used in page markdown",
+        ));
+    }
+    // page markdown is rendered under the name of its file
+    let file = Path::new(state.name()).parent().unwrap_or(Path::new("")).join(path);
+
+    match include::expand(&file, true) {
+        Ok(expanded) => {
+            let included = state.get_temp(INCLUDED).and_then(|v| v.try_iter().ok());
+            let mut included: Vec<Value> = included.into_iter().flatten().collect();
+            let dependencies = expanded.dependencies.iter();
+            included.extend(dependencies.map(|d| Value::from(d.display().to_string())));
+            state.set_temp(INCLUDED, Value::from(included));
+            Ok(expanded.source)
+        }
+        Err(e) => Err(Error::new(
+            ErrorKind::InvalidOperation,
+            format!("cannot include {}: {}", file.display(), e),
+        )),
+    }
+}
+
+fn markdown_options(spec: &MarkdownSpec) -> Options {
+    Options {
+        parse: ParseOptions {
+            constructs: Constructs {
+                frontmatter: true,
+                gfm_footnote_definition: spec.footnotes,
+                gfm_label_start_footnote: spec.footnotes,
+                math_flow: true,
+                math_text: true,
+                ..Constructs::gfm()
+            },
+            ..ParseOptions::gfm()
+        },
+        compile: CompileOptions {
+            // shortcodes expand to html, which has to reach the output as is
+            allow_dangerous_html: spec.templates,
+            ..CompileOptions::gfm()
+        },
+    }
 }
 
 pub fn is_empty(value: String) -> bool {
@@ -241,7 +694,10 @@ pub fn is_page(value: String) -> bool {
 mod tests {
     use super::*;
 
+    use crate::spec::{ManifestSpec, PageSpec, SectionField, SectionSpec};
+    use crate::testing::TempDir;
     use minijinja::{Environment, context};
+    use std::path::PathBuf;
 
     #[test]
     fn sections_iter() {
@@ -334,6 +790,297 @@ Second page in the root
         Ok(())
     }
 
+    #[test]
+    fn sidebar_skips_unlisted() -> anyhow::Result<()> {
+        let page = |name: &str, unlisted| PageSpec {
+            unlisted,
+            ..PageSpec::fixture(name, "1.md")
+        };
+        let pages = vec![page("Visible", false), page("Hidden", true)];
+        let spec = ManifestSpec::fixture("Unlisted", "1.md", pages);
+        let tree = Tree::from_spec(spec, PathBuf::from("spec1"), std::iter::empty());
+
+        let mut env = Environment::new();
+        env.add_test("page", is_page);
+        env.add_test("section", is_section);
+        env.add_template("sidebar.html", include_str!("./templates/sidebar.html"))?;
+        let tmpl = env.get_template("sidebar.html")?;
+        let res = tmpl.render(context! { tree => &tree, ctx => tree.context() })?;
+
+        assert!(res.contains("Visible"));
+        assert!(!res.contains("Hidden"));
+        Ok(())
+    }
+
+    #[test]
+    fn template_override() -> anyhow::Result<()> {
+        let tree = Tree::from_spec(
+            ManifestSpec::fixture("Override", "index.md", vec![]),
+            PathBuf::from("tests/fixtures/spec3"),
+            std::iter::empty(),
+        );
+        let env = tree.environment(&Value::UNDEFINED, false)?;
+        let res = env
+            .get_template("content.html")?
+            .render(context! { page => tree.main_page(), page_content => "Hi" })?;
+
+        assert_eq!(res, "Override: Hi");
+        assert!(env.get_template("sidebar.html").is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn missing_page_template() -> anyhow::Result<()> {
+        let page = |template: &str| PageSpec {
+            template: Some(template.to_string()),
+            ..PageSpec::fixture(template, "listed.md")
+        };
+        let tree = Tree::from_spec(
+            ManifestSpec::fixture("Templates", "index.md", vec![page("content.html")]),
+            PathBuf::from("tests/fixtures/spec3"),
+            std::iter::empty(),
+        );
+        let env = tree.environment(&Value::UNDEFINED, false)?;
+        assert!(tree.check_templates(&env).is_ok());
+
+        let pages = vec![page("content.html"), page("slides.html")];
+        let tree = Tree::from_spec(
+            ManifestSpec::fixture("Templates", "index.md", pages),
+            PathBuf::from("tests/fixtures/spec3"),
+            std::iter::empty(),
+        );
+        let err = tree.check_templates(&env).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1 missing template(s), add them to the `templates` directory"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn markdown_templates() -> anyhow::Result<()> {
+        let tree = Tree::from_spec(
+            ManifestSpec {
+                markdown: MarkdownSpec {
+                    templates: true,
+                    ..Default::default()
+                },
+                ..ManifestSpec::fixture("Shortcodes", "page.md", vec![])
+            },
+            PathBuf::from("tests/fixtures/spec4"),
+            std::iter::empty(),
+        );
+        let env = tree.environment(&Value::UNDEFINED, false)?;
+        let page = Value::from_serialize(tree.main_page());
+        let source = tree.page_markdown(&env, &Output::default(), tree.main_page().file(), &page)?;
+        let res = tree.markdown_to_html(&source, false)?;
+
+        assert!(res.starts_with("<h1>Shortcodes of Shortcodes</h1>"));
+        assert!(res.contains("<figure>\n<img src=\"tux.png\" alt=\"Tux\" loading=\"lazy\">"));
+        assert!(res.contains("<figcaption>Tux</figcaption>"));
+        assert!(res.contains("<div class=\"admonition warning\">"));
+        assert!(res.contains("<p>Back up <em>everything</em>.</p>"));
+        assert!(res.contains("<video class=\"embed\" src=\"talk.webm\""));
+        assert!(res.contains("<p>Shared <strong>setup</strong> steps.</p>"));
+        Ok(())
+    }
+
+    #[test]
+    fn markdown_extensions() -> anyhow::Result<()> {
+        let tree = |markdown| {
+            Tree::from_spec(
+                ManifestSpec {
+                    markdown,
+                    ..ManifestSpec::fixture("Extensions", "index.md", vec![])
+                },
+                PathBuf::from("tests/fixtures/spec3"),
+                std::iter::empty(),
+            )
+        };
+        let source = "# Title {#top}\n\nTerm\n: Definition[^1]\n\n[^1]: Note\n";
+
+        let res = tree(MarkdownSpec::default()).markdown_to_html(source, false)?;
+        assert!(res.starts_with("<h1>Title {#top}</h1>\n<p>Term\n: Definition<sup>"));
+
+        let res = tree(MarkdownSpec {
+            footnotes: false,
+            definition_lists: true,
+            heading_attributes: true,
+            ..Default::default()
+        })
+        .markdown_to_html(source, false)?;
+        assert!(res.starts_with("<h1 id=\"top\">Title</h1>\n<dl>\n<dt>Term</dt>"));
+        assert!(res.contains("<dd>Definition"));
+        assert!(!res.contains("<sup>"));
+        Ok(())
+    }
+
+    #[test]
+    fn section_intro_like_pages() -> anyhow::Result<()> {
+        let field = SectionField {
+            name: "Guide".to_string(),
+            desc: "".to_string(),
+            draft: false,
+            unlisted: false,
+            index: Some("guide.md".to_string()),
+            toc: true,
+            template: None,
+            page_template: None,
+        };
+        let section = Section::from_spec(
+            SectionSpec {
+                section: field,
+                pages: vec![],
+            },
+            std::iter::empty(),
+            Path::new("tests/fixtures/spec4/include"),
+            Path::new("tests/fixtures/spec4"),
+        );
+        let tree = Tree::from_spec(
+            ManifestSpec::fixture("Intro", "page.md", vec![]),
+            PathBuf::from("tests/fixtures/spec4"),
+            std::iter::once(section),
+        );
+        let outdir = TempDir::new("intro");
+        let out = Output::new(&outdir, false, None, false);
+        tree.render_sections(&outdir, &tree.environment(&Value::UNDEFINED, false)?, &out, "site")?;
+        let res = fs::read_to_string(outdir.join("include/index.html"))?;
+
+        assert!(res.contains("<h2>Setup</h2>"));
+        assert!(res.contains("<h3>Details</h3>\n<p>Nested.</p>"));
+        Ok(())
+    }
+
+    #[test]
+    fn script_in_base_template() -> anyhow::Result<()> {
+        let root = TempDir::new("script");
+        fs::create_dir_all(root.join("templates"))?;
+        fs::write(root.join("templates/base.html"), "<script>{{ script }}</script>")?;
+        fs::write(root.join("page.md"), "Hi")?;
+        let tree = Tree::from_spec(
+            ManifestSpec::fixture("Script", "page.md", vec![PageSpec::fixture("Page", "page.md")]),
+            root.to_path_buf(),
+            std::iter::empty(),
+        );
+        let outdir = root.join("out");
+        let out = Output::new(&outdir, false, None, false);
+        let env = tree.environment(&Value::UNDEFINED, false)?;
+        tree.render_pages(&outdir, &env, &out, "site", &[])?;
+        let res = fs::read_to_string(outdir.join("page.html"))?;
+
+        assert_eq!(res, format!("<script>{}</script>", SCRIPT_JS));
+        Ok(())
+    }
+
+    #[test]
+    fn term_links() -> anyhow::Result<()> {
+        let root = TempDir::new("terms");
+        fs::write(root.join("a.md"), "A")?;
+        fs::write(root.join("b.md"), "B")?;
+        let pages = vec![
+            PageSpec {
+                tags: vec!["Linux".to_string()],
+                categories: vec!["Notes".to_string()],
+                ..PageSpec::fixture("A", "a.md")
+            },
+            PageSpec {
+                tags: vec!["linux".to_string(), "secret".to_string()],
+                unlisted: true,
+                ..PageSpec::fixture("B", "b.md")
+            },
+        ];
+        let tree = Tree::from_spec(
+            ManifestSpec::fixture("Terms", "a.md", pages),
+            root.to_path_buf(),
+            std::iter::empty(),
+        );
+        let outdir = root.join("out");
+        let out = Output::new(&outdir, false, None, false);
+        let env = tree.environment(&Value::UNDEFINED, false)?;
+        tree.render_pages(&outdir, &env, &out, "site", &tree.taxonomies())?;
+        let a = fs::read_to_string(outdir.join("a.html"))?;
+        let b = fs::read_to_string(outdir.join("b.html"))?;
+
+        assert!(a.contains(r#"<a href="/tags/linux.html">#Linux</a>"#));
+        assert!(a.contains(r#"<a href="/categories/notes.html">#Notes</a>"#));
+        assert!(b.contains(r#"<a href="/tags/linux.html">#linux</a>"#));
+        assert!(b.contains("<span>#secret</span>"));
+        assert!(!b.contains("secret.html"));
+        Ok(())
+    }
+
+    #[test]
+    fn included_files_are_dependencies() -> anyhow::Result<()> {
+        let root = TempDir::new("included");
+        fs::write(root.join("page.md"), "Before {{ include(\"part.md\") }} after")?;
+        fs::write(root.join("part.md"), "old")?;
+        let page = PageSpec::fixture("Page", "page.md");
+        let tree = Tree::from_spec(
+            ManifestSpec {
+                markdown: MarkdownSpec {
+                    templates: true,
+                    ..Default::default()
+                },
+                ..ManifestSpec::fixture("Included", "page.md", vec![page])
+            },
+            root.to_path_buf(),
+            std::iter::empty(),
+        );
+        let outdir = root.join("out");
+        let build = || -> anyhow::Result<String> {
+            let out = Output::new(&outdir, false, Some(root.join("build.json")), false);
+            let env = tree.environment(&Value::UNDEFINED, false)?;
+            tree.render_pages(&outdir, &env, &out, "site", &[])?;
+            out.finish(None)?;
+            Ok(fs::read_to_string(outdir.join("page.html"))?)
+        };
+
+        assert!(build()?.contains("Before old after"));
+        fs::write(root.join("part.md"), "new")?;
+        assert!(build()?.contains("Before new after"));
+        Ok(())
+    }
+
+    #[test]
+    fn included_files_are_not_orphans() -> anyhow::Result<()> {
+        let root = TempDir::new("orphans");
+        fs::write(root.join("page.md"), "{{< include \"part.md\" >}}")?;
+        fs::write(root.join("part.md"), "Part")?;
+        fs::write(root.join("orphan.md"), "Orphan")?;
+        let page = PageSpec::fixture("Page", "page.md");
+        let mut tree = Tree::from_spec(
+            ManifestSpec::fixture("Orphans", "page.md", vec![page]),
+            root.to_path_buf(),
+            std::iter::empty(),
+        );
+        tree.set_orphans(vec![root.join("part.md"), root.join("orphan.md")]);
+        let outdir = root.join("out");
+        let out = Output::new(&outdir, false, None, false);
+        let env = tree.environment(&Value::UNDEFINED, false)?;
+        tree.render_pages(&outdir, &env, &out, "site", &[])?;
+
+        assert_eq!(tree.orphans_left(&out), vec![root.join("orphan.md")]);
+        Ok(())
+    }
+
+    #[test]
+    fn section_intro_without_toc() -> anyhow::Result<()> {
+        let tree = Tree::example_tree();
+        let env = tree.environment(&Value::UNDEFINED, false)?;
+        let sec = context! { name => "Intro", toc => false, pages => vec![tree.main_page()] };
+        let res = env.get_template("sec_index.html")?.render(context! {
+            ctx => tree.context(),
+            sec,
+            sec_content => tree.markdown_to_html("Welcome *here*.", false)?,
+        })?;
+
+        assert!(res.contains("<div class=\"sec-intro\">\n<p>Welcome <em>here</em>.</p>"));
+        assert!(!res.contains(tree.main_page().name()));
+        let style = format!("{}/{}", tree.href_prepend(), tree.assets(false)["style.css"]);
+        assert!(res.contains(&format!("<link rel=\"stylesheet\" href=\"{}\">", style)));
+        Ok(())
+    }
+
     #[test]
     fn test_inherit() -> anyhow::Result<()> {
         let exp = r"[title]
@@ -0,0 +1,219 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::fs;
+use std::path::Path;
+
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table, value};
+
+const MANIFEST: &str = r#"# This is the title of index.html in the root.
+title = ""
+# Appending the title means that any page title (name), for example "Schedule"
+# will become "Schedule | <title>".
+# This field is optional
+append_title = false
+
+# This will be rendered and saved as index.html in the root so that
+# going to the address of the website would display this page first.
+main_page = "landing.md"
+# The thing displayed in the footer. It's raw html, so be careful. Pages will also
+# append a string with the last modified date of said page.
+# This field is optional - in case it's missing, the footer will be disabled
+# footer_content = ""
+
+# This field prepends the string before every href, for websites which are not hosted at the root
+# of their domain. Be careful not to include the trailing slash.
+# This field is optional
+# href_prepend = ""
+
+# See example/manifest.toml in the wgen repository for the other settings.
+
+# Page definitions consist of:
+# * `name`, which becomes the title of said page,
+# * (optional) `desc`ription, displayed below the name of pages in sections,
+# * `path` relative to the manifest or spec file with the contents of the page.
+# `wgen new page <file> --name <name>` adds a page to the spec file next to it.
+"#;
+
+const SECTION: &str = r#"# A section has a name and an optional description. They are displayed at the top of the index
+# (table of contents) page for that section.
+[section]
+name = ""
+desc = ""
+
+# Pages work in the same way as in the `manifest.toml` file.
+"#;
+
+const SUBSECTION: &str = r#"# A subsection has a name and an optional description. They are displayed at the top of the
+# index (table of contents) page for that subsection and in the table of contents of its section.
+[subsection]
+name = ""
+desc = ""
+
+# Pages work in the same way as in the `manifest.toml` file.
+"#;
+
+const SPECS: &[&str] = &["manifest.toml", "section.toml", "subsection.toml"];
+
+/// Create a site in `dir`, with a manifest titled `title` and a main page, unless it exists.
+pub fn site(dir: &Path, title: &str) -> anyhow::Result<()> {
+    let manifest = dir.join("manifest.toml");
+    if manifest.exists() {
+        anyhow::bail!("{} already exists", manifest.display());
+    }
+
+    let mut doc: DocumentMut = MANIFEST.parse()?;
+    doc["title"] = value(title);
+    fs::create_dir_all(dir)?;
+    log::info!("Creating {}", manifest.display());
+    fs::write(&manifest, doc.to_string())?;
+    let landing = dir.join("landing.md");
+    if !landing.exists() {
+        page_file(&landing, title)?;
+    }
+    Ok(())
+}
+
+/// Create the section `dir` in the root of a site, or a subsection when `dir` is in a section.
+pub fn section(dir: &Path, name: &str, desc: &str) -> anyhow::Result<()> {
+    let parent = parent(dir);
+    let (template, file, table) = if parent.join("manifest.toml").is_file() {
+        (SECTION, "section.toml", "section")
+    } else if parent.join("section.toml").is_file() {
+        (SUBSECTION, "subsection.toml", "subsection")
+    } else {
+        anyhow::bail!(
+            "{} is neither the root of a site nor a section",
+            parent.display()
+        );
+    };
+    let spec = dir.join(file);
+    if spec.exists() {
+        anyhow::bail!("{} already exists", spec.display());
+    }
+
+    let mut doc: DocumentMut = template.parse()?;
+    doc[table]["name"] = value(name);
+    doc[table]["desc"] = value(desc);
+    fs::create_dir_all(dir)?;
+    log::info!("Creating {}", spec.display());
+    fs::write(&spec, doc.to_string())?;
+    Ok(())
+}
+
+/// Create the markdown file of a page, unless it exists, and add the page to the spec file in
+/// its directory, keeping the comments and formatting of the spec.
+pub fn page(file: &Path, name: &str, desc: &str) -> anyhow::Result<()> {
+    let dir = parent(file);
+    let Some(spec) = SPECS.iter().map(|s| dir.join(s)).find(|s| s.is_file()) else {
+        anyhow::bail!("no spec file in {} to add the page to", dir.display());
+    };
+    let Some(path) = file.file_name().and_then(|f| f.to_str()) else {
+        anyhow::bail!("{} is not a valid page file", file.display());
+    };
+
+    let mut doc: DocumentMut = fs::read_to_string(&spec)?.parse()?;
+    // comments after the last key belong to the end of the file, ahead of the first page is
+    // where they were written
+    let first = !doc.contains_key("page");
+    let trailing = doc.trailing().as_str().unwrap_or_default().to_string();
+    let pages = doc
+        .entry("page")
+        .or_insert(Item::ArrayOfTables(ArrayOfTables::new()));
+    let Some(pages) = pages.as_array_of_tables_mut() else {
+        anyhow::bail!("`page` in {} is not a list of tables", spec.display());
+    };
+    if pages
+        .iter()
+        .any(|p| p.get("path").and_then(Item::as_str) == Some(path))
+    {
+        anyhow::bail!("{} is already listed in {}", path, spec.display());
+    }
+
+    let mut page = Table::new();
+    page["name"] = value(name);
+    if !desc.is_empty() {
+        page["desc"] = value(desc);
+    }
+    page["path"] = value(path);
+    // separate it from the previous entry like the pages in the example
+    if first {
+        page.decor_mut().set_prefix(trailing + "\n");
+    } else {
+        page.decor_mut().set_prefix("\n");
+    }
+    pages.push(page);
+    if first {
+        doc.set_trailing("");
+    }
+
+    if !file.exists() {
+        page_file(file, name)?;
+    }
+    log::info!("Adding {} to {}", path, spec.display());
+    fs::write(&spec, doc.to_string())?;
+    Ok(())
+}
+
+fn page_file(file: &Path, name: &str) -> anyhow::Result<()> {
+    log::info!("Creating {}", file.display());
+    fs::write(file, format!("# {}\n", name))?;
+    Ok(())
+}
+
+fn parent(path: &Path) -> &Path {
+    match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::discover::{Options, Structure};
+    use crate::testing::TempDir;
+
+    #[test]
+    fn new_site() -> anyhow::Result<()> {
+        let root = TempDir::new("scaffold");
+        site(&root, "A \"quoted\" site")?;
+        section(&root.join("notes"), "Notes", "")?;
+        section(&root.join("notes/linux"), "Linux", "About Linux")?;
+        page(&root.join("about.md"), "About", "")?;
+        page(
+            &root.join("notes/linux/shell.md"),
+            "Shell",
+            "Using the shell",
+        )?;
+        page(&root.join("notes/linux/boot.md"), "Booting", "")?;
+        assert!(page(&root.join("notes/linux/boot.md"), "Again", "").is_err());
+        assert!(section(&root.join("notes/linux/deeper"), "Deeper", "").is_err());
+
+        let tree = Structure::collect_from(&root)?.into_tree(&Options::default())?;
+        let pages: Vec<&str> = tree.pages().map(|p| p.name()).collect();
+        assert_eq!(pages, ["About", "Shell", "Booting"]);
+        assert_eq!(tree.main_page().name(), "A \"quoted\" site");
+
+        let spec = fs::read_to_string(root.join("notes/linux/subsection.toml"))?;
+        assert!(spec.starts_with("# A subsection has a name"));
+        assert!(spec.ends_with(
+            "# Pages work in the same way as in the `manifest.toml` file.\n\n\
+             [[page]]\nname = \"Shell\"\ndesc = \"Using the shell\"\npath = \"shell.md\"\n\n\
+             [[page]]\nname = \"Booting\"\npath = \"boot.md\"\n"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn existing_main_page() -> anyhow::Result<()> {
+        let root = TempDir::new("landing");
+        fs::write(root.join("landing.md"), "# Mine\n")?;
+        site(&root, "Site")?;
+
+        assert_eq!(fs::read_to_string(root.join("landing.md"))?, "# Mine\n");
+        assert!(root.join("manifest.toml").is_file());
+        Ok(())
+    }
+}
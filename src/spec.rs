@@ -1,7 +1,16 @@
 // Copyright 2025 Szymon Sztuka (lysolaka)
 // Distributed under the terms of the GNU General Public License v3 or later
 
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
 use serde::Deserialize;
+use serde::de::{DeserializeOwned, Deserializer, Visitor};
+use toml::de::{DeTable, DeValue};
+
+use crate::diagnostic::{self, Diagnostic, Level};
 
 #[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct PageSpec {
@@ -9,6 +18,41 @@ pub struct PageSpec {
     #[serde(default)]
     pub desc: String,
     pub path: String,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub unlisted: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    pub template: Option<String>,
+    pub bibliography: Option<String>,
+}
+
+/// The TOML block at the top of a page file, enclosed in `+++` lines.
+#[derive(Debug, Deserialize, Default, PartialEq)]
+pub struct FrontMatter {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+}
+
+impl FrontMatter {
+    /// Parse the front matter of a page, if it has any.
+    pub fn from_content(content: &str) -> anyhow::Result<Option<Self>> {
+        let rest = content
+            .strip_prefix("+++\n")
+            .or_else(|| content.strip_prefix("+++\r\n"));
+        let Some(rest) = rest else {
+            return Ok(None);
+        };
+        let Some(end) = rest.find("\n+++") else {
+            anyhow::bail!("front matter is not closed with `+++`");
+        };
+        Ok(Some(toml::from_str(&rest[..end])?))
+    }
 }
 
 #[derive(Debug, Deserialize, PartialEq)]
@@ -21,21 +65,101 @@ pub struct ManifestSpec {
     pub footer_content: String,
     #[serde(default)]
     pub href_prepend: String,
-    #[serde(rename = "page")]
+    #[serde(default)]
+    pub minify: bool,
+    #[serde(default)]
+    pub keep: Vec<String>,
+    #[serde(default)]
+    pub site_json: bool,
+    #[serde(default)]
+    pub markdown: MarkdownSpec,
+    #[serde(default)]
+    pub images: ImagesSpec,
+    #[serde(rename = "page", default)]
     pub pages: Vec<PageSpec>,
 }
 
+/// The `[markdown]` table of the manifest, controlling how page contents are converted.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct MarkdownSpec {
+    #[serde(default)]
+    pub templates: bool,
+    #[serde(default = "default_true")]
+    pub footnotes: bool,
+    #[serde(default)]
+    pub definition_lists: bool,
+    #[serde(default)]
+    pub heading_attributes: bool,
+    pub bibliography: Option<String>,
+    #[serde(default)]
+    pub citations: CitationStyle,
+    #[serde(default = "default_diagrams")]
+    pub diagrams: BTreeMap<String, String>,
+}
+
+fn default_diagrams() -> BTreeMap<String, String> {
+    BTreeMap::from([("dot".to_string(), "dot -Tsvg".to_string())])
+}
+
+/// The `[images]` table of the manifest, controlling what happens to images in pages.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ImagesSpec {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_widths")]
+    pub widths: Vec<u32>,
+    #[serde(default = "default_true")]
+    pub webp: bool,
+}
+
+fn default_widths() -> Vec<u32> {
+    vec![480, 960, 1600]
+}
+
+impl Default for ImagesSpec {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            widths: default_widths(),
+            webp: true,
+        }
+    }
+}
+
+/// How `[@key]` citations and the list of references look.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CitationStyle {
+    #[default]
+    Numbered,
+    AuthorYear,
+}
+
+impl Default for MarkdownSpec {
+    fn default() -> Self {
+        Self {
+            templates: false,
+            footnotes: true,
+            definition_lists: false,
+            heading_attributes: false,
+            bibliography: None,
+            citations: CitationStyle::Numbered,
+            diagrams: default_diagrams(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct SectionSpec {
     pub section: SectionField,
-    #[serde(rename = "page")]
+    #[serde(rename = "page", default)]
     pub pages: Vec<PageSpec>,
 }
 
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct SubsectionSpec {
     pub subsection: SectionField,
-    #[serde(rename = "page")]
+    #[serde(rename = "page", default)]
     pub pages: Vec<PageSpec>,
 }
 
@@ -44,6 +168,229 @@ pub struct SectionField {
     pub name: String,
     #[serde(default)]
     pub desc: String,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub unlisted: bool,
+    pub index: Option<String>,
+    #[serde(default = "default_true")]
+    pub toc: bool,
+    pub template: Option<String>,
+    pub page_template: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Lists the keys a spec file understands, so that unknown ones can be reported.
+pub trait Keys {
+    fn keys(path: &[&str]) -> &'static [&'static str];
+}
+
+impl Keys for ManifestSpec {
+    fn keys(path: &[&str]) -> &'static [&'static str] {
+        match path {
+            ["page"] => fields::<PageSpec>(),
+            ["markdown"] => fields::<MarkdownSpec>(),
+            ["images"] => fields::<ImagesSpec>(),
+            _ => fields::<Self>(),
+        }
+    }
+}
+
+impl Keys for SectionSpec {
+    fn keys(path: &[&str]) -> &'static [&'static str] {
+        match path {
+            ["page"] => fields::<PageSpec>(),
+            ["section"] => fields::<SectionField>(),
+            _ => fields::<Self>(),
+        }
+    }
+}
+
+impl Keys for SubsectionSpec {
+    fn keys(path: &[&str]) -> &'static [&'static str] {
+        match path {
+            ["page"] => fields::<PageSpec>(),
+            ["subsection"] => fields::<SectionField>(),
+            _ => fields::<Self>(),
+        }
+    }
+}
+
+/// The keys of a struct as its `Deserialize` implementation reads them, in declaration order.
+fn fields<T: DeserializeOwned>() -> &'static [&'static str] {
+    let fields = Cell::new(&[][..]);
+    // the error only stops the deserialization once the fields are known
+    let _ = T::deserialize(Fields(&fields));
+    fields.get()
+}
+
+struct Fields<'a>(&'a Cell<&'static [&'static str]>);
+
+impl<'de> Deserializer<'de> for Fields<'_> {
+    type Error = serde::de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+        Err(serde::de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.set(fields);
+        Err(serde::de::Error::custom("fields read"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Read and deserialize a spec file, reporting problems with a snippet of the file.
+pub fn read<T: DeserializeOwned + Keys>(path: &Path, strict: bool) -> anyhow::Result<T> {
+    let source = fs::read_to_string(path)?;
+    parse(&source, path, strict)
+}
+
+fn parse<T: DeserializeOwned + Keys>(source: &str, path: &Path, strict: bool) -> anyhow::Result<T> {
+    let toml_error = |e: toml::de::Error| {
+        let diag = Diagnostic::new(Level::Error, e.message().trim_end(), path);
+        match e.span() {
+            Some(span) => diag.with_span(source, span),
+            None => diag,
+        }
+    };
+
+    let de = toml::de::Deserializer::parse(source).map_err(toml_error)?;
+    let mut unknown = Vec::new();
+    let spec: T = serde_ignored::deserialize(de, |p| unknown.push(segments(&p)))
+        .map_err(toml_error)?;
+
+    if unknown.is_empty() {
+        return Ok(spec);
+    }
+
+    let doc = DeTable::parse(source).ok();
+    let level = if strict { Level::Error } else { Level::Warning };
+    for segments in &unknown {
+        let keys: Vec<&str> = segments
+            .iter()
+            .filter_map(|s| match s {
+                Segment::Key(k) => Some(k.as_str()),
+                Segment::Index(_) => None,
+            })
+            .collect();
+        let Some((key, parent)) = keys.split_last() else {
+            continue;
+        };
+
+        let mut diag = Diagnostic::new(level, format!("unknown key `{}`", key), path);
+        if let Some(span) = doc.as_ref().and_then(|d| locate(d.get_ref(), segments)) {
+            diag = diag.with_span(source, span);
+        }
+        if let Some(s) = diagnostic::suggest(key, T::keys(parent)) {
+            diag = diag.with_help(format!("did you mean `{}`?", s));
+        }
+
+        match level {
+            Level::Error => log::error!("{}", diag),
+            Level::Warning => log::warn!("{}", diag),
+        }
+    }
+
+    if strict {
+        anyhow::bail!("{} unknown key(s) in {}", unknown.len(), path.display());
+    }
+    Ok(spec)
+}
+
+fn segments(path: &serde_ignored::Path) -> Vec<Segment> {
+    let mut segments = match path {
+        serde_ignored::Path::Root => return Vec::new(),
+        serde_ignored::Path::Seq { parent, .. }
+        | serde_ignored::Path::Map { parent, .. }
+        | serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => segments(parent),
+    };
+
+    match path {
+        serde_ignored::Path::Seq { index, .. } => segments.push(Segment::Index(*index)),
+        serde_ignored::Path::Map { key, .. } => segments.push(Segment::Key(key.clone())),
+        _ => (),
+    }
+    segments
+}
+
+fn locate(table: &DeTable, segments: &[Segment]) -> Option<std::ops::Range<usize>> {
+    let (Segment::Key(key), rest) = segments.split_first()? else {
+        return None;
+    };
+    let (k, v) = table.iter().find(|(k, _)| k.get_ref() == key)?;
+    if rest.is_empty() {
+        return Some(k.span());
+    }
+
+    let mut value = v.get_ref();
+    let mut rest = rest;
+    while let Some((Segment::Index(i), tail)) = rest.split_first() {
+        value = value.as_array()?.get(*i)?.get_ref();
+        rest = tail;
+    }
+
+    match value {
+        DeValue::Table(t) => locate(t, rest),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+impl PageSpec {
+    pub fn fixture(name: &str, path: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            desc: "".to_string(),
+            path: path.to_string(),
+            draft: false,
+            unlisted: false,
+            tags: vec![],
+            categories: vec![],
+            template: None,
+            bibliography: None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl ManifestSpec {
+    pub fn fixture(title: &str, main_page: &str, pages: Vec<PageSpec>) -> Self {
+        Self {
+            title: title.to_string(),
+            append_title: false,
+            main_page: main_page.to_string(),
+            footer_content: "".to_string(),
+            href_prepend: "".to_string(),
+            minify: false,
+            keep: Vec::new(),
+            site_json: false,
+            markdown: Default::default(),
+            images: Default::default(),
+            pages,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -51,7 +398,6 @@ mod tests {
     use super::*;
     use std::fs;
     use std::path::Path;
-    use toml;
 
     #[test]
     fn read_manifest() -> anyhow::Result<()> {
@@ -64,16 +410,33 @@ mod tests {
             main_page: "index.md".to_string(),
             footer_content: "&copy; lysolaka. Contact me: +69 420 024 420".to_string(),
             href_prepend: "/~home".to_string(),
+            minify: false,
+            keep: Vec::new(),
+            site_json: false,
+            markdown: Default::default(),
+            images: Default::default(),
             pages: vec![
                 PageSpec {
                     name: "First page 1.md".to_string(),
                     desc: "Generic description".to_string(),
                     path: "1.md".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    tags: vec![],
+                    categories: vec![],
+                    template: None,
+                    bibliography: None,
                 },
                 PageSpec {
                     name: "Second page in the root".to_string(),
                     desc: "".to_string(),
                     path: "2.md".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    tags: vec![],
+                    categories: vec![],
+                    template: None,
+                    bibliography: None,
                 },
             ],
         };
@@ -88,17 +451,38 @@ mod tests {
         let section: SectionSpec = toml::from_str(&section)?;
 
         let spec = SectionSpec {
-            section: SectionField { name: "D1 section".to_string(), desc: "Could be no description".to_string() },
+            section: SectionField {
+                name: "D1 section".to_string(),
+                desc: "Could be no description".to_string(),
+                draft: false,
+                unlisted: false,
+                index: None,
+                toc: true,
+                template: None,
+                page_template: None,
+            },
             pages: vec![
                 PageSpec {
                     name: "D1-1".to_string(),
                     desc: "there will be more ones".to_string(),
                     path: "1.md".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    tags: vec![],
+                    categories: vec![],
+                    template: None,
+                    bibliography: None,
                 },
                 PageSpec {
                     name: "Second page in the d1 section".to_string(),
                     desc: "".to_string(),
                     path: "2.md".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    tags: vec![],
+                    categories: vec![],
+                    template: None,
+                    bibliography: None,
                 },
             ],
         };
@@ -113,17 +497,38 @@ mod tests {
         let subsection: SubsectionSpec = toml::from_str(&subsection)?;
 
         let spec = SubsectionSpec {
-            subsection: SectionField { name: "subsection s1".to_string(), desc: "".to_string() },
+            subsection: SectionField {
+                name: "subsection s1".to_string(),
+                desc: "".to_string(),
+                draft: false,
+                unlisted: false,
+                index: None,
+                toc: true,
+                template: None,
+                page_template: None,
+            },
             pages: vec![
                 PageSpec {
                     name: "D1-S1-1".to_string(),
                     desc: "this is the most 1's".to_string(),
                     path: "1.md".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    tags: vec![],
+                    categories: vec![],
+                    template: None,
+                    bibliography: None,
                 },
                 PageSpec {
                     name: "Second page in the d1/s1 subsection".to_string(),
                     desc: "".to_string(),
                     path: "2.md".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    tags: vec![],
+                    categories: vec![],
+                    template: None,
+                    bibliography: None,
                 },
             ],
         };
@@ -131,4 +536,62 @@ mod tests {
         assert_eq!(subsection, spec);
         Ok(())
     }
+
+    #[test]
+    fn unknown_keys() -> anyhow::Result<()> {
+        let source = "[section]\nname = \"A\"\ndescr = \"B\"\n\n[[page]]\nname = \"P\"\npath = \"p.md\"\n";
+        let path = Path::new("section.toml");
+
+        let lenient: SectionSpec = parse(source, path, false)?;
+        assert_eq!(lenient.section.desc, "");
+        assert!(parse::<SectionSpec>(source, path, true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn keys_follow_fields() {
+        assert_eq!(SectionSpec::keys(&[]), ["section", "page"]);
+        assert_eq!(ManifestSpec::keys(&["images"]), ["enabled", "widths", "webp"]);
+        assert_eq!(SubsectionSpec::keys(&["page"])[..3], ["name", "desc", "path"]);
+    }
+
+    #[test]
+    fn locate_unknown_key() -> anyhow::Result<()> {
+        let source = "title = \"T\"\nmain_page = \"i.md\"\n\n[[page]]\nname = \"P\"\npaht = \"p.md\"\n";
+        let doc = DeTable::parse(source)?;
+        let segments = [
+            Segment::Key("page".to_string()),
+            Segment::Index(0),
+            Segment::Key("paht".to_string()),
+        ];
+        let span = locate(doc.get_ref(), &segments).unwrap();
+
+        assert_eq!(&source[span], "paht");
+        Ok(())
+    }
+
+    #[test]
+    fn syntax_error_location() {
+        let source = "[section]\nname = \"A\"\ndesc = \n";
+        let err = parse::<SectionSpec>(source, Path::new("s.toml"), false).unwrap_err();
+        let err = err.to_string();
+
+        assert!(err.starts_with("error: "));
+        assert!(err.contains("--> s.toml:3:"));
+        assert!(err.contains("3 | desc = "));
+    }
+
+    #[test]
+    fn front_matter() -> anyhow::Result<()> {
+        let content = "+++\ntags = [\"linux\", \"gentoo\"]\n+++\n# Title\n";
+        let exp = FrontMatter {
+            tags: vec!["linux".to_string(), "gentoo".to_string()],
+            categories: vec![],
+        };
+
+        assert_eq!(FrontMatter::from_content(content)?, Some(exp));
+        assert_eq!(FrontMatter::from_content("# Title\n+++\n")?, None);
+        assert!(FrontMatter::from_content("+++\ntags = []\n").is_err());
+        Ok(())
+    }
 }
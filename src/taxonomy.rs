@@ -0,0 +1,176 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use crate::tree::*;
+
+/// A grouping of pages across the section hierarchy, like tags or categories.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Taxonomy<'a> {
+    name: &'static str,
+    href: String,
+    terms: Vec<Term<'a>>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Term<'a> {
+    name: &'a str,
+    slug: String,
+    href: String,
+    count: usize,
+    pages: Vec<&'a Page>,
+}
+
+/// A term of a single page, `href` is only set when the term has a page in its taxonomy.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct TermLink<'a> {
+    name: &'a str,
+    href: Option<&'a str>,
+}
+
+impl<'a> Taxonomy<'a> {
+    /// Group the listed pages of `tree` by the terms returned from `terms`.
+    pub fn collect(name: &'static str, tree: &'a Tree, terms: impl Fn(&Page) -> &[String]) -> Self {
+        let mut collected: Vec<Term<'a>> = Vec::new();
+
+        for page in tree.pages().filter(|p| !p.is_unlisted()) {
+            for term in terms(page) {
+                let slug = slugify(term);
+                match collected.iter_mut().find(|t| t.slug == slug) {
+                    Some(t) => t.pages.push(page),
+                    None => collected.push(Term {
+                        name: term,
+                        href: format!("/{}/{}.html", name, slug),
+                        slug,
+                        count: 0,
+                        pages: vec![page],
+                    }),
+                }
+            }
+        }
+
+        for term in collected.iter_mut() {
+            term.count = term.pages.len();
+        }
+        collected.sort_by_key(|t| t.name.to_lowercase());
+
+        Self {
+            name,
+            href: format!("/{}/", name),
+            terms: collected,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    pub fn href(&self) -> &str {
+        &self.href
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Term<'a>> {
+        self.terms.iter()
+    }
+
+    /// Link `terms` to their pages, terms only used by unlisted pages have none.
+    pub fn links<'t>(&'t self, terms: &'t [String]) -> Vec<TermLink<'t>> {
+        terms
+            .iter()
+            .map(|name| {
+                let slug = slugify(name);
+                let term = self.terms.iter().find(|t| t.slug == slug);
+                TermLink {
+                    name,
+                    href: term.map(|t| t.href.as_str()),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Term<'_> {
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    pub fn href(&self) -> &str {
+        &self.href
+    }
+}
+
+impl Tree {
+    pub fn taxonomies(&self) -> Vec<Taxonomy<'_>> {
+        vec![
+            Taxonomy::collect("tags", self, |p| p.tags()),
+            Taxonomy::collect("categories", self, |p| p.categories()),
+        ]
+    }
+}
+
+/// Turn a term into something usable in a file name: lowercase alphanumerics separated by dashes.
+pub fn slugify(value: &str) -> String {
+    let mut slug = String::new();
+    for c in value.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    if slug.ends_with('-') {
+        slug.pop();
+    }
+    slug
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::spec::{ManifestSpec, PageSpec};
+    use std::path::PathBuf;
+
+    fn page(name: &str, tags: &[&str]) -> PageSpec {
+        PageSpec {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..PageSpec::fixture(name, "1.md")
+        }
+    }
+
+    #[test]
+    fn slugs() {
+        assert_eq!(slugify("Gentoo"), "gentoo");
+        assert_eq!(slugify("C++ & Rust!"), "c-rust");
+        assert_eq!(slugify("  spaced out  "), "spaced-out");
+    }
+
+    #[test]
+    fn collect_tags() {
+        let spec = ManifestSpec::fixture(
+            "Tags",
+            "1.md",
+            vec![
+                page("A", &["linux", "Projects"]),
+                page("B", &["projects"]),
+                page("C", &[]),
+            ],
+        );
+        let tree = Tree::from_spec(spec, PathBuf::from("spec1"), std::iter::empty());
+        let tags = Taxonomy::collect("tags", &tree, |p| p.tags());
+
+        let terms: Vec<(&str, &str, usize)> =
+            tags.iter().map(|t| (t.name, t.href(), t.count)).collect();
+        let exp = vec![
+            ("linux", "/tags/linux.html", 1),
+            ("Projects", "/tags/projects.html", 2),
+        ];
+
+        assert_eq!(terms, exp);
+        assert!(Taxonomy::collect("categories", &tree, |p| p.categories()).is_empty());
+    }
+}
@@ -15,9 +15,19 @@ pub struct Page {
     path: PathBuf,
     href: String,
     date: String,
+    draft: bool,
+    unlisted: bool,
+    tags: Vec<String>,
+    categories: Vec<String>,
+    template: Option<String>,
+    bibliography: Option<PathBuf>,
 }
 
 impl Page {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn href(&self) -> &str {
         &self.href
     }
@@ -26,6 +36,36 @@ impl Page {
         &self.path
     }
 
+    pub fn is_draft(&self) -> bool {
+        self.draft
+    }
+
+    pub fn is_unlisted(&self) -> bool {
+        self.unlisted
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn categories(&self) -> &[String] {
+        &self.categories
+    }
+
+    pub fn template(&self) -> Option<&str> {
+        self.template.as_deref()
+    }
+
+    pub fn bibliography(&self) -> Option<&Path> {
+        self.bibliography.as_deref()
+    }
+
+    fn inherit_template(&mut self, template: Option<&str>) {
+        if self.template.is_none() {
+            self.template = template.map(str::to_string);
+        }
+    }
+
     pub fn from_spec(spec: PageSpec, location: &Path, root: &Path) -> Self {
         let path = location.join(&spec.path);
         let href = if let Ok(p) = path.strip_prefix(root) {
@@ -45,12 +85,31 @@ impl Page {
             Err(_) => "Unknown".to_string(),
         };
 
+        let mut tags = spec.tags;
+        let mut categories = spec.categories;
+        if let Ok(content) = fs::read_to_string(&path) {
+            match FrontMatter::from_content(&content) {
+                Ok(Some(front_matter)) => {
+                    merge(&mut tags, front_matter.tags);
+                    merge(&mut categories, front_matter.categories);
+                }
+                Ok(None) => (),
+                Err(e) => log::warn!("Ignoring the front matter of {}: {}", path.display(), e),
+            }
+        }
+
         Self {
             name: spec.name,
             desc: spec.desc,
             path,
             href,
             date,
+            draft: spec.draft,
+            unlisted: spec.unlisted,
+            tags,
+            categories,
+            template: spec.template,
+            bibliography: spec.bibliography.map(|b| location.join(b)),
         }
     }
 
@@ -74,6 +133,20 @@ impl Page {
             path,
             href: "/".to_string(),
             date,
+            draft: false,
+            unlisted: false,
+            tags: Vec::new(),
+            categories: Vec::new(),
+            template: None,
+            bibliography: None,
+        }
+    }
+}
+
+fn merge(into: &mut Vec<String>, from: Vec<String>) {
+    for item in from {
+        if !into.contains(&item) {
+            into.push(item);
         }
     }
 }
@@ -93,6 +166,11 @@ pub struct Subsection {
     desc: String,
     path: PathBuf,
     href: String,
+    draft: bool,
+    unlisted: bool,
+    index: Option<PathBuf>,
+    toc: bool,
+    template: Option<String>,
     pages: Vec<Page>,
 }
 
@@ -104,10 +182,15 @@ impl Subsection {
             format!("/{}/", location.display())
         };
 
+        let page_template = spec.subsection.page_template.as_deref();
         let pages: Vec<Page> = spec
             .pages
             .into_iter()
             .map(|p| Page::from_spec(p, location, root))
+            .map(|mut p| {
+                p.inherit_template(page_template);
+                p
+            })
             .collect();
 
         log::trace!("Found {} pages in {}", pages.len(), location.display());
@@ -117,15 +200,44 @@ impl Subsection {
             desc: spec.subsection.desc,
             path: location.to_path_buf(),
             href,
+            draft: spec.subsection.draft,
+            unlisted: spec.subsection.unlisted,
+            index: spec.subsection.index.map(|i| location.join(i)),
+            toc: spec.subsection.toc,
+            template: spec.subsection.template,
             pages,
         }
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn href(&self) -> &str {
         &self.href
     }
 
-    pub fn iter(&self) -> std::slice::Iter<Page> {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_draft(&self) -> bool {
+        self.draft
+    }
+
+    pub fn is_unlisted(&self) -> bool {
+        self.unlisted
+    }
+
+    pub fn index(&self) -> Option<&Path> {
+        self.index.as_deref()
+    }
+
+    pub fn template(&self) -> Option<&str> {
+        self.template.as_deref()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Page> {
         self.pages.iter()
     }
 }
@@ -143,6 +255,11 @@ pub struct Section {
     desc: String,
     path: PathBuf,
     href: String,
+    draft: bool,
+    unlisted: bool,
+    index: Option<PathBuf>,
+    toc: bool,
+    template: Option<String>,
     entries: Vec<SectionEntry>,
 }
 
@@ -159,15 +276,22 @@ impl Section {
             format!("/{}/", location.display())
         };
 
-        let pages = spec
-            .pages
-            .into_iter()
-            .map(|p| SectionEntry::Page(Page::from_spec(p, location, root)));
+        let page_template = spec.section.page_template.as_deref();
+        let pages = spec.pages.into_iter().map(|p| {
+            let mut page = Page::from_spec(p, location, root);
+            page.inherit_template(page_template);
+            SectionEntry::Page(page)
+        });
 
         log::trace!("Found {} pages in {}", pages.len(), location.display());
 
         let entries: Vec<SectionEntry> = subsections
-            .map(SectionEntry::Subsection)
+            .map(|mut s| {
+                s.pages
+                    .iter_mut()
+                    .for_each(|p| p.inherit_template(page_template));
+                SectionEntry::Subsection(s)
+            })
             .chain(pages)
             .collect();
 
@@ -176,15 +300,44 @@ impl Section {
             desc: spec.section.desc,
             path: location.to_path_buf(),
             href,
+            draft: spec.section.draft,
+            unlisted: spec.section.unlisted,
+            index: spec.section.index.map(|i| location.join(i)),
+            toc: spec.section.toc,
+            template: spec.section.template,
             entries,
         }
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn href(&self) -> &str {
         &self.href
     }
 
-    pub fn iter(&self) -> std::slice::Iter<SectionEntry> {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_draft(&self) -> bool {
+        self.draft
+    }
+
+    pub fn is_unlisted(&self) -> bool {
+        self.unlisted
+    }
+
+    pub fn index(&self) -> Option<&Path> {
+        self.index.as_deref()
+    }
+
+    pub fn template(&self) -> Option<&str> {
+        self.template.as_deref()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, SectionEntry> {
         self.entries.iter()
     }
 }
@@ -203,6 +356,18 @@ pub struct Tree {
     append_title: bool,
     href_prepend: String,
     footer_content: String,
+    #[serde(skip)]
+    minify: bool,
+    #[serde(skip)]
+    keep: Vec<String>,
+    #[serde(skip)]
+    site_json: bool,
+    #[serde(skip)]
+    markdown: MarkdownSpec,
+    #[serde(skip)]
+    images: ImagesSpec,
+    #[serde(skip)]
+    orphans: Vec<PathBuf>,
     main_page: Page,
     entries: Vec<TreeEntry>,
 }
@@ -229,16 +394,60 @@ impl Tree {
             title: spec.title,
             append_title: spec.append_title,
             href_prepend: spec.href_prepend,
+            minify: spec.minify,
+            keep: spec.keep,
+            site_json: spec.site_json,
             footer_content: spec.footer_content,
+            markdown: spec.markdown,
+            images: spec.images,
+            orphans: Vec::new(),
             main_page,
             entries,
         }
     }
 
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
     pub fn main_page(&self) -> &Page {
         &self.main_page
     }
 
+    pub fn href_prepend(&self) -> &str {
+        &self.href_prepend
+    }
+
+    pub fn markdown(&self) -> &MarkdownSpec {
+        &self.markdown
+    }
+
+    pub fn images(&self) -> &ImagesSpec {
+        &self.images
+    }
+
+    pub fn minify(&self) -> bool {
+        self.minify
+    }
+
+    pub fn keep(&self) -> &[String] {
+        &self.keep
+    }
+
+    pub fn site_json(&self) -> bool {
+        self.site_json
+    }
+
+    /// Markdown files next to the spec files which are not the source of any page, they are
+    /// only known to be orphans once no page includes them either.
+    pub fn orphans(&self) -> &[PathBuf] {
+        &self.orphans
+    }
+
+    pub fn set_orphans(&mut self, orphans: Vec<PathBuf>) {
+        self.orphans = orphans;
+    }
+
     pub fn context(&self) -> Context<'_> {
         Context {
             title: &self.title,
@@ -248,9 +457,40 @@ impl Tree {
         }
     }
 
-    pub fn iter(&self) -> std::slice::Iter<TreeEntry> {
+    pub fn iter(&self) -> std::slice::Iter<'_, TreeEntry> {
         self.entries.iter()
     }
+
+    /// Remove draft pages, sections and subsections, returning how many were removed.
+    pub fn drop_drafts(&mut self) -> usize {
+        let count = self.entries.len();
+        self.entries.retain(|e| match e {
+            TreeEntry::Page(p) => !p.draft,
+            TreeEntry::Section(s) => !s.draft,
+        });
+        let mut dropped = count - self.entries.len();
+
+        for entry in self.entries.iter_mut() {
+            if let TreeEntry::Section(section) = entry {
+                let count = section.entries.len();
+                section.entries.retain(|e| match e {
+                    SectionEntry::Page(p) => !p.draft,
+                    SectionEntry::Subsection(s) => !s.draft,
+                });
+                dropped += count - section.entries.len();
+
+                for entry in section.entries.iter_mut() {
+                    if let SectionEntry::Subsection(subsection) = entry {
+                        let count = subsection.pages.len();
+                        subsection.pages.retain(|p| !p.draft);
+                        dropped += count - subsection.pages.len();
+                    }
+                }
+            }
+        }
+
+        dropped
+    }
 }
 
 #[derive(serde::Serialize)]
@@ -261,66 +501,19 @@ pub struct Context<'a> {
     footer_content: &'a str,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    use crate::spec;
-    use std::path::{Path, PathBuf};
-
-    #[test]
-    fn page_from_spec() {
-        let spec = spec::PageSpec {
-            name: "Imaginary Name".to_string(),
-            desc: "Hello!".to_string(),
-            path: "imaginary_file.md".to_string(),
-        };
-
-        let page = Page::from_spec(spec.clone(), Path::new("in/s1/"), Path::new("in/"));
-
-        let exp = Page {
-            name: "Imaginary Name".to_string(),
-            desc: "Hello!".to_string(),
-            path: PathBuf::from("in/s1/imaginary_file.md"),
-            href: "/s1/imaginary_file.html".to_string(),
-            date: "Unknown".to_string(),
-        };
-
-        assert_eq!(page, exp);
-
-        let page = Page::from_spec(spec.clone(), Path::new(""), Path::new(""));
-
-        let exp = Page {
-            name: "Imaginary Name".to_string(),
-            desc: "Hello!".to_string(),
-            path: PathBuf::from("imaginary_file.md"),
-            href: "/imaginary_file.html".to_string(),
-            date: "Unknown".to_string(),
-        };
-
-        assert_eq!(page, exp);
-    }
-}
-
-#[cfg(test)]
-impl Page {
-    pub fn name(&self) -> &str {
-        &self.name
-    }
-}
-
 #[cfg(test)]
 impl Subsection {
-    pub fn name(&self) -> &str {
-        &self.name
-    }
-
     pub fn subsection_read_spec1_expected() -> Self {
         Self {
             name: "subsection s1".to_string(),
             desc: "".to_string(),
             path: PathBuf::from("spec1/d1/s1"),
             href: "/d1/s1/".to_string(),
+            draft: false,
+            unlisted: false,
+            index: None,
+            toc: true,
+            template: None,
             pages: vec![
                 Page {
                     name: "D1-S1-1".to_string(),
@@ -328,6 +521,12 @@ impl Subsection {
                     path: PathBuf::from("spec1/d1/s1/1.md"),
                     href: "/d1/s1/1.html".to_string(),
                     date: "1.09.2025 20:34".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    tags: vec![],
+                    categories: vec![],
+                    template: None,
+                    bibliography: None,
                 },
                 Page {
                     name: "Second page in the d1/s1 subsection".to_string(),
@@ -335,6 +534,12 @@ impl Subsection {
                     path: PathBuf::from("spec1/d1/s1/2.md"),
                     href: "/d1/s1/2.html".to_string(),
                     date: "1.09.2025 20:34".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    tags: vec![],
+                    categories: vec![],
+                    template: None,
+                    bibliography: None,
                 },
             ],
         }
@@ -346,12 +551,23 @@ impl Subsection {
             desc: "SZAJSE".to_string(),
             path: PathBuf::from("spec2/d2/s1"),
             href: "/d2/s1/".to_string(),
+            draft: false,
+            unlisted: false,
+            index: None,
+            toc: true,
+            template: None,
             pages: vec![Page {
                 name: "1 MD".to_string(),
                 desc: "The first and only page here".to_string(),
                 path: PathBuf::from("spec2/d2/s1/1.md"),
                 href: "/d2/s1/1.html".to_string(),
                 date: "1.09.2025 20:34".to_string(),
+                draft: false,
+                unlisted: false,
+                tags: vec![],
+                categories: vec![],
+                template: None,
+                bibliography: None,
             }],
         }
     }
@@ -359,22 +575,28 @@ impl Subsection {
 
 #[cfg(test)]
 impl Section {
-    pub fn name(&self) -> &str {
-        &self.name
-    }
-
     pub fn section_read_spec_expected() -> Self {
         Self {
             name: "D1 section".to_string(),
             desc: "Shit section".to_string(),
             path: PathBuf::from("spec2/d1"),
             href: "/d1/".to_string(),
+            draft: false,
+            unlisted: false,
+            index: None,
+            toc: true,
+            template: None,
             entries: vec![
                 SectionEntry::Subsection(Subsection {
                     name: "S1 subsection".to_string(),
                     desc: "Shit shit shit".to_string(),
                     path: PathBuf::from("spec2/d1/s1"),
                     href: "/d1/s1/".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    index: None,
+                    toc: true,
+                    template: None,
                     pages: vec![
                         Page {
                             name: "1 MD".to_string(),
@@ -382,6 +604,12 @@ impl Section {
                             path: PathBuf::from("spec2/d1/s1/1.md"),
                             href: "/d1/s1/1.html".to_string(),
                             date: "1.09.2025 20:34".to_string(),
+                            draft: false,
+                            unlisted: false,
+                            tags: vec![],
+                            categories: vec![],
+                            template: None,
+                            bibliography: None,
                         },
                         Page {
                             name: "2nd md".to_string(),
@@ -389,6 +617,12 @@ impl Section {
                             path: PathBuf::from("spec2/d1/s1/2.md"),
                             href: "/d1/s1/2.html".to_string(),
                             date: "1.09.2025 20:34".to_string(),
+                            draft: false,
+                            unlisted: false,
+                            tags: vec![],
+                            categories: vec![],
+                            template: None,
+                            bibliography: None,
                         },
                     ],
                 }),
@@ -397,6 +631,11 @@ impl Section {
                     desc: "Shit".to_string(),
                     path: PathBuf::from("spec2/d1/s2"),
                     href: "/d1/s2/".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    index: None,
+                    toc: true,
+                    template: None,
                     pages: vec![
                         Page {
                             name: "1 EMDE".to_string(),
@@ -404,6 +643,12 @@ impl Section {
                             path: PathBuf::from("spec2/d1/s2/1.md"),
                             href: "/d1/s2/1.html".to_string(),
                             date: "1.09.2025 20:34".to_string(),
+                            draft: false,
+                            unlisted: false,
+                            tags: vec![],
+                            categories: vec![],
+                            template: None,
+                            bibliography: None,
                         },
                         Page {
                             name: "second md".to_string(),
@@ -411,6 +656,12 @@ impl Section {
                             path: PathBuf::from("spec2/d1/s2/2.md"),
                             href: "/d1/s2/2.html".to_string(),
                             date: "1.09.2025 20:34".to_string(),
+                            draft: false,
+                            unlisted: false,
+                            tags: vec![],
+                            categories: vec![],
+                            template: None,
+                            bibliography: None,
                         },
                     ],
                 }),
@@ -420,6 +671,12 @@ impl Section {
                     path: PathBuf::from("spec2/d1/1.md"),
                     href: "/d1/1.html".to_string(),
                     date: "1.09.2025 20:34".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    tags: vec![],
+                    categories: vec![],
+                    template: None,
+                    bibliography: None,
                 }),
                 SectionEntry::Page(Page {
                     name: "2nd markdown".to_string(),
@@ -427,6 +684,12 @@ impl Section {
                     path: PathBuf::from("spec2/d1/2.md"),
                     href: "/d1/2.html".to_string(),
                     date: "1.09.2025 20:34".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    tags: vec![],
+                    categories: vec![],
+                    template: None,
+                    bibliography: None,
                 }),
             ],
         }
@@ -441,6 +704,12 @@ impl Tree {
             title: "WGEN Webpage".to_string(),
             append_title: false,
             href_prepend: "/~home".to_string(),
+            minify: false,
+            keep: Vec::new(),
+            site_json: false,
+            markdown: Default::default(),
+            images: Default::default(),
+            orphans: Vec::new(),
             footer_content: "&copy; lysolaka. Contact me: +69 420 024 420".to_string(),
             main_page: Page {
                 name: "WGEN Webpage".to_string(),
@@ -448,6 +717,12 @@ impl Tree {
                 path: PathBuf::from("spec2/index.md"),
                 href: "/".to_string(),
                 date: "3.09.2025 16:14".to_string(),
+                draft: false,
+                unlisted: false,
+                tags: vec![],
+                categories: vec![],
+                template: None,
+                bibliography: None,
             },
             entries: vec![
                 TreeEntry::Section(Section {
@@ -455,12 +730,22 @@ impl Tree {
                     desc: "Shit section".to_string(),
                     path: PathBuf::from("spec2/d1"),
                     href: "/d1/".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    index: None,
+                    toc: true,
+                    template: None,
                     entries: vec![
                         SectionEntry::Subsection(Subsection {
                             name: "S1 subsection".to_string(),
                             desc: "Shit shit shit".to_string(),
                             path: PathBuf::from("spec2/d1/s1"),
                             href: "/d1/s1/".to_string(),
+                            draft: false,
+                            unlisted: false,
+                            index: None,
+                            toc: true,
+                            template: None,
                             pages: vec![
                                 Page {
                                     name: "1 MD".to_string(),
@@ -468,6 +753,12 @@ impl Tree {
                                     path: PathBuf::from("spec2/d1/s1/1.md"),
                                     href: "/d1/s1/1.html".to_string(),
                                     date: "29.08.2025 08:45".to_string(),
+                                    draft: false,
+                                    unlisted: false,
+                                    tags: vec![],
+                                    categories: vec![],
+                                    template: None,
+                                    bibliography: None,
                                 },
                                 Page {
                                     name: "2nd md".to_string(),
@@ -475,6 +766,12 @@ impl Tree {
                                     path: PathBuf::from("spec2/d1/s1/2.md"),
                                     href: "/d1/s1/2.html".to_string(),
                                     date: "29.08.2025 08:45".to_string(),
+                                    draft: false,
+                                    unlisted: false,
+                                    tags: vec![],
+                                    categories: vec![],
+                                    template: None,
+                                    bibliography: None,
                                 },
                             ],
                         }),
@@ -483,6 +780,11 @@ impl Tree {
                             desc: "Shit".to_string(),
                             path: PathBuf::from("spec2/d1/s2"),
                             href: "/d1/s2/".to_string(),
+                            draft: false,
+                            unlisted: false,
+                            index: None,
+                            toc: true,
+                            template: None,
                             pages: vec![
                                 Page {
                                     name: "1 EMDE".to_string(),
@@ -490,6 +792,12 @@ impl Tree {
                                     path: PathBuf::from("spec2/d1/s2/1.md"),
                                     href: "/d1/s2/1.html".to_string(),
                                     date: "29.08.2025 08:45".to_string(),
+                                    draft: false,
+                                    unlisted: false,
+                                    tags: vec![],
+                                    categories: vec![],
+                                    template: None,
+                                    bibliography: None,
                                 },
                                 Page {
                                     name: "second md".to_string(),
@@ -497,6 +805,12 @@ impl Tree {
                                     path: PathBuf::from("spec2/d1/s2/2.md"),
                                     href: "/d1/s2/2.html".to_string(),
                                     date: "29.08.2025 08:45".to_string(),
+                                    draft: false,
+                                    unlisted: false,
+                                    tags: vec![],
+                                    categories: vec![],
+                                    template: None,
+                                    bibliography: None,
                                 },
                             ],
                         }),
@@ -506,6 +820,12 @@ impl Tree {
                             path: PathBuf::from("spec2/d1/1.md"),
                             href: "/d1/1.html".to_string(),
                             date: "29.08.2025 08:45".to_string(),
+                            draft: false,
+                            unlisted: false,
+                            tags: vec![],
+                            categories: vec![],
+                            template: None,
+                            bibliography: None,
                         }),
                         SectionEntry::Page(Page {
                             name: "2nd markdown".to_string(),
@@ -513,6 +833,12 @@ impl Tree {
                             path: PathBuf::from("spec2/d1/2.md"),
                             href: "/d1/2.html".to_string(),
                             date: "29.08.2025 08:45".to_string(),
+                            draft: false,
+                            unlisted: false,
+                            tags: vec![],
+                            categories: vec![],
+                            template: None,
+                            bibliography: None,
                         }),
                     ],
                 }),
@@ -521,18 +847,34 @@ impl Tree {
                     desc: "Shittier section".to_string(),
                     path: PathBuf::from("spec2/d2"),
                     href: "/d2/".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    index: None,
+                    toc: true,
+                    template: None,
                     entries: vec![
                         SectionEntry::Subsection(Subsection {
                             name: "d1/S1 subsection".to_string(),
                             desc: "SZAJSE".to_string(),
                             path: PathBuf::from("spec2/d2/s1"),
                             href: "/d2/s1/".to_string(),
+                            draft: false,
+                            unlisted: false,
+                            index: None,
+                            toc: true,
+                            template: None,
                             pages: vec![Page {
                                 name: "1 MD".to_string(),
                                 desc: "The first and only page here".to_string(),
                                 path: PathBuf::from("spec2/d2/s1/1.md"),
                                 href: "/d2/s1/1.html".to_string(),
                                 date: "29.08.2025 08:45".to_string(),
+                                draft: false,
+                                unlisted: false,
+                                tags: vec![],
+                                categories: vec![],
+                                template: None,
+                                bibliography: None,
                             }],
                         }),
                         SectionEntry::Page(Page {
@@ -541,6 +883,12 @@ impl Tree {
                             path: PathBuf::from("spec2/d2/1.md"),
                             href: "/d2/1.html".to_string(),
                             date: "29.08.2025 08:45".to_string(),
+                            draft: false,
+                            unlisted: false,
+                            tags: vec![],
+                            categories: vec![],
+                            template: None,
+                            bibliography: None,
                         }),
                         SectionEntry::Page(Page {
                             name: "2nd markdown".to_string(),
@@ -548,6 +896,12 @@ impl Tree {
                             path: PathBuf::from("spec2/d2/2.md"),
                             href: "/d2/2.html".to_string(),
                             date: "29.08.2025 08:45".to_string(),
+                            draft: false,
+                            unlisted: false,
+                            tags: vec![],
+                            categories: vec![],
+                            template: None,
+                            bibliography: None,
                         }),
                     ],
                 }),
@@ -557,6 +911,12 @@ impl Tree {
                     path: PathBuf::from("spec2/1.md"),
                     href: "/1.html".to_string(),
                     date: "29.08.2025 08:45".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    tags: vec![],
+                    categories: vec![],
+                    template: None,
+                    bibliography: None,
                 }),
                 TreeEntry::Page(Page {
                     name: "Second page in the root".to_string(),
@@ -564,6 +924,12 @@ impl Tree {
                     path: PathBuf::from("spec2/2.md"),
                     href: "/2.html".to_string(),
                     date: "29.08.2025 08:45".to_string(),
+                    draft: false,
+                    unlisted: false,
+                    tags: vec![],
+                    categories: vec![],
+                    template: None,
+                    bibliography: None,
                 }),
             ],
         }
@@ -575,6 +941,12 @@ impl Tree {
             title: "Example Webpage".to_string(),
             append_title: false,
             href_prepend: "/~home".to_string(),
+            minify: false,
+            keep: Vec::new(),
+            site_json: false,
+            markdown: Default::default(),
+            images: Default::default(),
+            orphans: Vec::new(),
             footer_content: "&copy; lysolaka. Contact me: +69 420 024 420".to_string(),
             main_page: Page {
                 name: "Example Webpage".to_string(),
@@ -582,6 +954,12 @@ impl Tree {
                 path: PathBuf::from("example/landing.md"),
                 href: "/".to_string(),
                 date: "6.09.2025 13:09".to_string(),
+                draft: false,
+                unlisted: false,
+                tags: vec![],
+                categories: vec![],
+                template: None,
+                bibliography: None,
             },
             entries: vec![
                 TreeEntry::Section(
@@ -590,6 +968,11 @@ impl Tree {
                         desc: "All my personal projects, completed or in progress".to_string(),
                         path: PathBuf::from("example/projects"),
                         href: "/projects/".to_string(),
+                        draft: false,
+                        unlisted: false,
+                        index: None,
+                        toc: true,
+                        template: None,
                         entries: vec![
                             SectionEntry::Page(
                                 Page {
@@ -598,6 +981,12 @@ impl Tree {
                                     path: PathBuf::from("example/projects/catalyst.md"),
                                     href: "/projects/catalyst.html".to_string(),
                                     date: "6.09.2025 13:03".to_string(),
+                                    draft: false,
+                                    unlisted: false,
+                                    tags: vec![],
+                                    categories: vec![],
+                                    template: None,
+                                    bibliography: None,
                                 },
                             ),
                             SectionEntry::Page(
@@ -607,6 +996,12 @@ impl Tree {
                                     path: PathBuf::from("example/projects/wgen.md"),
                                     href: "/projects/wgen.html".to_string(),
                                     date: "6.09.2025 12:58".to_string(),
+                                    draft: false,
+                                    unlisted: false,
+                                    tags: vec![],
+                                    categories: vec![],
+                                    template: None,
+                                    bibliography: None,
                                 },
                             ),
                             SectionEntry::Page(
@@ -616,6 +1011,12 @@ impl Tree {
                                     path: PathBuf::from("example/projects/llvmtoo.md"),
                                     href: "/projects/llvmtoo.html".to_string(),
                                     date: "6.09.2025 12:56".to_string(),
+                                    draft: false,
+                                    unlisted: false,
+                                    tags: vec![],
+                                    categories: vec![],
+                                    template: None,
+                                    bibliography: None,
                                 },
                             ),
                         ],
@@ -627,6 +1028,11 @@ impl Tree {
                         desc: "Various tutorials written in hope to be useful".to_string(),
                         path: PathBuf::from("example/tutorials"),
                         href: "/tutorials/".to_string(),
+                        draft: false,
+                        unlisted: false,
+                        index: None,
+                        toc: true,
+                        template: None,
                         entries: vec![
                             SectionEntry::Subsection(
                                 Subsection {
@@ -634,6 +1040,11 @@ impl Tree {
                                     desc: "Tutorials regarding the GNU/Linux operating system".to_string(),
                                     path: PathBuf::from("example/tutorials/linux"),
                                     href: "/tutorials/linux/".to_string(),
+                                    draft: false,
+                                    unlisted: false,
+                                    index: None,
+                                    toc: true,
+                                    template: None,
                                     pages: vec![
                                         Page {
                                             name: "Choosing your distribution".to_string(),
@@ -641,6 +1052,12 @@ impl Tree {
                                             path: PathBuf::from("example/tutorials/linux/distro.md"),
                                             href: "/tutorials/linux/distro.html".to_string(),
                                             date: "6.09.2025 15:25".to_string(),
+                                            draft: false,
+                                            unlisted: false,
+                                            tags: vec![],
+                                            categories: vec![],
+                                            template: None,
+                                            bibliography: None,
                                         },
                                         Page {
                                             name: "Why Gentoo is the best?".to_string(),
@@ -648,6 +1065,12 @@ impl Tree {
                                             path: PathBuf::from("example/tutorials/linux/gentoo.md"),
                                             href: "/tutorials/linux/gentoo.html".to_string(),
                                             date: "6.09.2025 15:26".to_string(),
+                                            draft: false,
+                                            unlisted: false,
+                                            tags: vec![],
+                                            categories: vec![],
+                                            template: None,
+                                            bibliography: None,
                                         },
                                         Page {
                                             name: "Online Linux tutorials".to_string(),
@@ -655,6 +1078,12 @@ impl Tree {
                                             path: PathBuf::from("example/tutorials/linux/tuts.md"),
                                             href: "/tutorials/linux/tuts.html".to_string(),
                                             date: "6.09.2025 15:26".to_string(),
+                                            draft: false,
+                                            unlisted: false,
+                                            tags: vec![],
+                                            categories: vec![],
+                                            template: None,
+                                            bibliography: None,
                                         },
                                     ],
                                 },
@@ -666,6 +1095,12 @@ impl Tree {
                                     path: PathBuf::from("example/tutorials/maths.md"),
                                     href: "/tutorials/maths.html".to_string(),
                                     date: "6.09.2025 15:26".to_string(),
+                                    draft: false,
+                                    unlisted: false,
+                                    tags: vec![],
+                                    categories: vec![],
+                                    template: None,
+                                    bibliography: None,
                                 },
                             ),
                             SectionEntry::Page(
@@ -675,6 +1110,12 @@ impl Tree {
                                     path: PathBuf::from("example/tutorials/toxic.md"),
                                     href: "/tutorials/toxic.html".to_string(),
                                     date: "6.09.2025 15:32".to_string(),
+                                    draft: false,
+                                    unlisted: false,
+                                    tags: vec![],
+                                    categories: vec![],
+                                    template: None,
+                                    bibliography: None,
                                 },
                             ),
                         ],
@@ -687,6 +1128,12 @@ impl Tree {
                         path: PathBuf::from("example/schedule.md"),
                         href: "/schedule.html".to_string(),
                         date: "6.09.2025 12:15".to_string(),
+                        draft: false,
+                        unlisted: false,
+                        tags: vec![],
+                        categories: vec![],
+                        template: None,
+                        bibliography: None,
                     },
                 ),
                 TreeEntry::Page(
@@ -696,9 +1143,133 @@ impl Tree {
                         path: PathBuf::from("example/contact.md"),
                         href: "/contact.html".to_string(),
                         date: "6.09.2025 12:22".to_string(),
+                        draft: false,
+                        unlisted: false,
+                        tags: vec![],
+                        categories: vec![],
+                        template: None,
+                        bibliography: None,
                     },
                 ),
             ],
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::spec;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn page_from_spec() {
+        let spec = spec::PageSpec {
+            desc: "Hello!".to_string(),
+            ..spec::PageSpec::fixture("Imaginary Name", "imaginary_file.md")
+        };
+
+        let page = Page::from_spec(spec.clone(), Path::new("in/s1/"), Path::new("in/"));
+
+        let exp = Page {
+            name: "Imaginary Name".to_string(),
+            desc: "Hello!".to_string(),
+            path: PathBuf::from("in/s1/imaginary_file.md"),
+            href: "/s1/imaginary_file.html".to_string(),
+            date: "Unknown".to_string(),
+            draft: false,
+            unlisted: false,
+            tags: vec![],
+            categories: vec![],
+            template: None,
+            bibliography: None,
+        };
+
+        assert_eq!(page, exp);
+
+        let page = Page::from_spec(spec.clone(), Path::new(""), Path::new(""));
+
+        let exp = Page {
+            name: "Imaginary Name".to_string(),
+            desc: "Hello!".to_string(),
+            path: PathBuf::from("imaginary_file.md"),
+            href: "/imaginary_file.html".to_string(),
+            date: "Unknown".to_string(),
+            draft: false,
+            unlisted: false,
+            tags: vec![],
+            categories: vec![],
+            template: None,
+            bibliography: None,
+        };
+
+        assert_eq!(page, exp);
+    }
+
+    #[test]
+    fn page_template_inheritance() {
+        let page = |path: &str, template: Option<&str>| spec::PageSpec {
+            template: template.map(str::to_string),
+            ..spec::PageSpec::fixture(path, path)
+        };
+        let field = |page_template: Option<&str>| spec::SectionField {
+            name: "Section".to_string(),
+            desc: "".to_string(),
+            draft: false,
+            unlisted: false,
+            index: None,
+            toc: true,
+            template: None,
+            page_template: page_template.map(str::to_string),
+        };
+
+        let subsection = Subsection::from_spec(
+            spec::SubsectionSpec {
+                subsection: field(None),
+                pages: vec![page("a.md", None), page("b.md", Some("slides.html"))],
+            },
+            Path::new("in/s1/sub"),
+            Path::new("in"),
+        );
+        let section = Section::from_spec(
+            spec::SectionSpec {
+                section: field(Some("wide.html")),
+                pages: vec![page("c.md", None)],
+            },
+            std::iter::once(subsection),
+            Path::new("in/s1"),
+            Path::new("in"),
+        );
+
+        let templates: Vec<(&str, Option<&str>)> = section
+            .iter()
+            .flat_map(|e| match e {
+                SectionEntry::Page(p) => vec![p],
+                SectionEntry::Subsection(s) => s.iter().collect(),
+            })
+            .map(|p| (p.name(), p.template()))
+            .collect();
+        let exp = vec![
+            ("a.md", Some("wide.html")),
+            ("b.md", Some("slides.html")),
+            ("c.md", Some("wide.html")),
+        ];
+
+        assert_eq!(templates, exp);
+    }
+
+    #[test]
+    fn tree_to_toml() -> anyhow::Result<()> {
+        let tree = Tree::example_tree();
+        let table: toml::Table = toml::from_str(&toml::to_string(&tree)?)?;
+
+        // TOML has no null, so fields which are not set are left out
+        let subsection = &table["entries"][1]["entries"][0];
+        assert_eq!(table["entries"].as_array().map(Vec::len), Some(4));
+        assert_eq!(subsection["type"].as_str(), Some("Subsection"));
+        assert_eq!(subsection["pages"][2]["href"].as_str(), Some("/tutorials/linux/tuts.html"));
+        assert!(subsection.get("index").is_none());
+        Ok(())
+    }
+}
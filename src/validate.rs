@@ -0,0 +1,302 @@
+// Copyright 2025 Szymon Sztuka (lysolaka)
+// Distributed under the terms of the GNU General Public License v3 or later
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::diagnostic::{Diagnostic, Level};
+use crate::discover::normalize;
+use crate::taxonomy::slugify;
+use crate::tree::*;
+
+impl Tree {
+    /// Check the tree for problems that would otherwise only show up halfway through rendering.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let issues = self.issues();
+        if issues.is_empty() {
+            return Ok(());
+        }
+
+        for issue in &issues {
+            log::error!("{}", issue);
+        }
+        anyhow::bail!("found {} problem(s) in the site tree", issues.len())
+    }
+
+    fn issues(&self) -> Vec<Diagnostic> {
+        let mut issues = Vec::new();
+        // output file -> (spec file declaring it, name)
+        let mut outputs: HashMap<PathBuf, Vec<(PathBuf, String)>> = HashMap::new();
+
+        let manifest = self.root().join("manifest.toml");
+        if !self.main_page().file().is_file() {
+            issues.push(Diagnostic::new(
+                Level::Error,
+                format!(
+                    "main_page `{}` does not exist",
+                    self.main_page().file().display()
+                ),
+                &manifest,
+            ));
+        }
+        outputs
+            .entry(output_file(self.main_page().href()))
+            .or_default()
+            .push((manifest.clone(), self.main_page().name().to_string()));
+
+        let mut check = |page: &Page, spec: &Path| {
+            check_page(page, spec, self.root(), &mut issues);
+            outputs
+                .entry(output_file(page.href()))
+                .or_default()
+                .push((spec.to_path_buf(), page.name().to_string()));
+        };
+
+        for entry in self.iter() {
+            match entry {
+                TreeEntry::Page(page) => check(page, &manifest),
+                TreeEntry::Section(section) => {
+                    let spec = section.path().join("section.toml");
+                    for entry in section.iter() {
+                        match entry {
+                            SectionEntry::Page(page) => check(page, &spec),
+                            SectionEntry::Subsection(subsection) => {
+                                let spec = subsection.path().join("subsection.toml");
+                                for page in subsection.iter() {
+                                    check(page, &spec);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for (spec, index) in self
+            .sections()
+            .filter_map(|s| Some((s.path().join("section.toml"), s.index()?)))
+            .chain(
+                self.subsections()
+                    .filter_map(|s| Some((s.path().join("subsection.toml"), s.index()?))),
+            )
+        {
+            if !index.is_file() {
+                issues.push(Diagnostic::new(
+                    Level::Error,
+                    format!("index `{}` does not exist", index.display()),
+                    &spec,
+                ));
+            }
+        }
+
+        for section in self.sections() {
+            outputs
+                .entry(output_file(section.href()))
+                .or_default()
+                .push((
+                    section.path().join("section.toml"),
+                    section.name().to_string(),
+                ));
+        }
+        for subsection in self.subsections() {
+            outputs
+                .entry(output_file(subsection.href()))
+                .or_default()
+                .push((
+                    subsection.path().join("subsection.toml"),
+                    subsection.name().to_string(),
+                ));
+        }
+
+        for taxonomy in self.taxonomies().iter().filter(|t| !t.is_empty()) {
+            outputs
+                .entry(output_file(taxonomy.href()))
+                .or_default()
+                .push((manifest.clone(), taxonomy.name().to_string()));
+            for term in taxonomy.iter() {
+                outputs
+                    .entry(output_file(term.href()))
+                    .or_default()
+                    .push((manifest.clone(), format!("{}: {}", taxonomy.name(), term.name())));
+            }
+        }
+
+        let mut duplicates: Vec<_> = outputs.into_iter().filter(|(_, v)| v.len() > 1).collect();
+        duplicates.sort();
+        for (output, declared) in duplicates {
+            let names: Vec<String> = declared.iter().map(|(_, n)| format!("`{}`", n)).collect();
+            let (spec, _) = &declared[1];
+            issues.push(
+                Diagnostic::new(
+                    Level::Error,
+                    format!(
+                        "{} would all be written to `{}`",
+                        names.join(", "),
+                        output.display()
+                    ),
+                    spec,
+                )
+                .with_help("give the pages distinct paths"),
+            );
+        }
+
+        issues
+    }
+}
+
+fn check_page(page: &Page, spec: &Path, root: &Path, issues: &mut Vec<Diagnostic>) {
+    if !is_inside(page.file(), root) {
+        issues.push(Diagnostic::new(
+            Level::Error,
+            format!(
+                "page `{}` points outside of the site root: `{}`",
+                page.name(),
+                page.file().display()
+            ),
+            spec,
+        ));
+    } else if !page.file().is_file() {
+        issues.push(Diagnostic::new(
+            Level::Error,
+            format!(
+                "page `{}` points to a missing file `{}`",
+                page.name(),
+                page.file().display()
+            ),
+            spec,
+        ));
+    }
+
+    for (kind, terms) in [("tag", page.tags()), ("category", page.categories())] {
+        for term in terms.iter().filter(|t| slugify(t).is_empty()) {
+            issues.push(Diagnostic::new(
+                Level::Error,
+                format!(
+                    "{} `{}` of page `{}` has no letters or digits to name its page after",
+                    kind,
+                    term,
+                    page.name()
+                ),
+                spec,
+            ));
+        }
+    }
+}
+
+fn is_inside(path: &Path, root: &Path) -> bool {
+    let Ok(rel) = path.strip_prefix(root) else {
+        return false;
+    };
+
+    let mut depth = 0usize;
+    for component in rel.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => (),
+            Component::ParentDir if depth > 0 => depth -= 1,
+            _ => return false,
+        }
+    }
+
+    match (path.canonicalize(), root.canonicalize()) {
+        (Ok(path), Ok(root)) => path.starts_with(root),
+        _ => true,
+    }
+}
+
+fn output_file(href: &str) -> PathBuf {
+    // all hrefs start with a slash; `a/./b.html` and `b.html` are the same file
+    let p = normalize(Path::new(href.strip_prefix('/').unwrap_or(href)));
+    if href.ends_with('/') {
+        p.join("index.html")
+    } else {
+        p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::spec::{ManifestSpec, PageSpec};
+
+    #[test]
+    fn valid_tree() {
+        let tree = Tree::structure_into_tree_expect();
+
+        assert_eq!(tree.issues(), vec![]);
+    }
+
+    #[test]
+    fn invalid_tree() {
+        let page = PageSpec::fixture;
+        let spec = ManifestSpec::fixture(
+            "Broken",
+            "missing.md",
+            vec![
+                page("First", "1.md"),
+                page("Again first", "1.md"),
+                page("Escaping", "../README.md"),
+                page("Missing", "none.md"),
+            ],
+        );
+        let tree = Tree::from_spec(spec, PathBuf::from("spec1"), std::iter::empty());
+        let issues: Vec<String> = tree.issues().iter().map(|i| i.to_string()).collect();
+
+        assert_eq!(issues.len(), 4);
+        assert!(issues[0].contains("main_page `spec1/missing.md` does not exist"));
+        assert!(issues[1].contains("page `Escaping` points outside of the site root"));
+        assert!(issues[2].contains("page `Missing` points to a missing file"));
+        assert!(issues[3].contains("`First`, `Again first` would all be written to `1.html`"));
+    }
+
+    #[test]
+    fn same_output_spelled_differently() {
+        let page = PageSpec::fixture;
+        let spec = ManifestSpec::fixture(
+            "Dots",
+            "1.md",
+            vec![page("Plain", "2.md"), page("Dotted", "./d1/../2.md")],
+        );
+        let tree = Tree::from_spec(spec, PathBuf::from("spec1"), std::iter::empty());
+        let issues: Vec<String> = tree.issues().iter().map(|i| i.to_string()).collect();
+
+        assert!(
+            issues
+                .iter()
+                .any(|i| i.contains("`Plain`, `Dotted` would all be written to `2.html`"))
+        );
+    }
+
+    #[test]
+    fn taxonomy_outputs() {
+        let spec = ManifestSpec::fixture(
+            "Tags",
+            "1.md",
+            vec![
+                PageSpec {
+                    tags: vec!["linux".to_string(), "?!".to_string()],
+                    ..PageSpec::fixture("A", "1.md")
+                },
+                PageSpec::fixture("Linux notes", "tags/linux.md"),
+            ],
+        );
+        let tree = Tree::from_spec(spec, PathBuf::from("spec1"), std::iter::empty());
+        let issues: Vec<String> = tree.issues().iter().map(|i| i.to_string()).collect();
+
+        assert!(issues.iter().any(|i| i.contains("tag `?!` of page `A` has no letters")));
+        assert!(issues.iter().any(|i| i.contains(
+            "`Linux notes`, `tags: linux` would all be written to `tags/linux.html`"
+        )));
+    }
+
+    #[test]
+    fn inside_root() {
+        assert!(is_inside(Path::new("spec1/d1/../1.md"), Path::new("spec1")));
+        assert!(!is_inside(
+            Path::new("spec1/d1/../../README.md"),
+            Path::new("spec1")
+        ));
+        assert!(!is_inside(Path::new("/etc/passwd"), Path::new("spec1")));
+    }
+}